@@ -17,7 +17,25 @@ addEventListener('resize', _ => {
 });
 
 const MOUSE_UP_EVENT = 0;
+const KEY_DOWN_EVENT = 1;
+const KEY_UP_EVENT = 2;
+const MOUSE_DOWN_EVENT = 3;
+const MOUSE_MOVE_EVENT = 4;
+
+// keys without a character, see `key_from_code` in wasm_backend.rs
+const SPECIAL_KEYS = {
+  'Enter': -1,
+  'Escape': -2,
+  'Backspace': -3,
+  'Tab': -4,
+  'ArrowLeft': -5,
+  'ArrowRight': -6,
+  'ArrowUp': -7,
+  'ArrowDown': -8,
+};
 var event_queue = [];
+// loaded by `_load_image`, drawn by their index
+var images = [];
 
 const event_queue_size = () => {
   let size = 0;
@@ -35,6 +53,62 @@ addEventListener('mouseup', e => {
   event_queue.push(data);
 });
 
+addEventListener('mousedown', e => {
+  if (e.button != 0) return;
+  // keeps the browser from dragging the canvas image around
+  e.preventDefault();
+  let x = e.clientX;
+  let y = e.clientY;
+  let data = new Uint8Array(new Uint32Array([MOUSE_DOWN_EVENT, e.button, x, y]).buffer)
+  event_queue.push(data);
+});
+
+addEventListener('mousemove', e => {
+  let x = e.clientX;
+  let y = e.clientY;
+  let data = new Uint8Array(new Uint32Array([MOUSE_MOVE_EVENT, x, y]).buffer)
+  event_queue.push(data);
+});
+
+
+const key_code = e => {
+  if (e.key in SPECIAL_KEYS) return SPECIAL_KEYS[e.key];
+  if (e.key.length === 1) return e.key.toLowerCase().codePointAt(0);
+  return -100;
+};
+
+const push_key_event = (type, e) => {
+  let mods = (e.ctrlKey ? 1 : 0) | (e.shiftKey ? 2 : 0) | (e.altKey ? 4 : 0);
+  let data = new Uint8Array(new Int32Array([type, key_code(e), mods]).buffer);
+  event_queue.push(data);
+};
+
+addEventListener('keydown', e => {
+  if (e.ctrlKey && (e.key === 'z' || e.key === 'y')) e.preventDefault();
+  push_key_event(KEY_DOWN_EVENT, e);
+});
+
+addEventListener('keyup', e => push_key_event(KEY_UP_EVENT, e));
+
+// the page's query as arguments, see `args` in wasm_backend.rs
+const query_args = () => {
+  let args = [];
+  for (const [key, value] of new URLSearchParams(location.search)) {
+    args.push(`--${key}`, value);
+  }
+  return new TextEncoder().encode(args.join('\n'));
+};
+
+// the connection to a game server, see `WebSocketClient` in wasm_backend.rs
+var socket = null;
+// received messages not yet taken by the game
+var socket_queue = [];
+// messages sent before the connection was open
+var socket_pending = [];
+var socket_closed = false;
+
+const socket_queue_data = () =>
+  new TextEncoder().encode(socket_queue.map(m => m + '\n').join(''));
 
 const get_string = (ptr, len) => {
   let arr = new Uint8Array(exports.memory.buffer);
@@ -80,6 +154,17 @@ const run_wasm = () => WebAssembly.instantiateStreaming(fetch(PATH), {
       let buf = load_sync(get_string(path_ptr, path_len));
       return buf.length;
     },
+    '_write_file': (path_ptr, path_len, buf_ptr, buf_len) => {
+      // the browser cannot write files, so offer the data as a download
+      let path = get_string(path_ptr, path_len);
+      let data = new Uint8Array(exports.memory.buffer, buf_ptr, buf_len).slice();
+      let url = URL.createObjectURL(new Blob([data]));
+      let a = document.createElement('a');
+      a.href = url;
+      a.download = path.split('/').pop();
+      a.click();
+      URL.revokeObjectURL(url);
+    },
     '_set_draw_color': (r, g, b) => {
       ctx.fillStyle = `rgb(${r}, ${g}, ${b})`;
       ctx.strokeStyle = `rgb(${r}, ${g}, ${b})`;
@@ -120,40 +205,86 @@ const run_wasm = () => WebAssembly.instantiateStreaming(fetch(PATH), {
       ctx.fillStyle = `rgb(${r}, ${g}, ${b})`;
       ctx.fillText(txt, x, y);
     },
-    '_draw_point': (x, y) => {
-      ctx.fillRect(x, y, 1, 1);
-    },
     '_draw_line': (x1, y1, x2, y2) => {
       ctx.beginPath();
       ctx.moveTo(x1, y1);
       ctx.lineTo(x2, y2);
       ctx.stroke();
     },
-    '_render_png': (data_ptr, data_len, x, y, w, h) => {
-      let data = new Uint8Array(exports.memory.buffer, data_ptr, data_len);
-      let str = '';
-      for (let i = 0; i < data_len; i++) {
-        str += String.fromCharCode(data[i]);
+    '_fill_polygon': (points_ptr, points_len) => {
+      let coords = new Int32Array(exports.memory.buffer, points_ptr, points_len * 2);
+      // text rendering changes the fill style
+      ctx.fillStyle = ctx.strokeStyle;
+      ctx.beginPath();
+      ctx.moveTo(coords[0], coords[1]);
+      for (let i = 1; i < points_len; i++) {
+        ctx.lineTo(coords[2 * i], coords[2 * i + 1]);
       }
-
-      let b64 = btoa(str);
-      let url = `data:image/png;base64,${b64}`;
+      ctx.closePath();
+      ctx.fill();
+    },
+    '_fill_circle': (x, y, r) => {
+      ctx.fillStyle = ctx.strokeStyle;
+      ctx.beginPath();
+      ctx.arc(x, y, r, 0, 2 * Math.PI);
+      ctx.fill();
+    },
+    '_load_image': (data_ptr, data_len) => {
+      let data = new Uint8Array(exports.memory.buffer, data_ptr, data_len).slice();
       let img = new Image();
-      img.src = url;
-
-      ctx.drawImage(img, x, y, w, h);
+      img.src = URL.createObjectURL(new Blob([data], { type: 'image/png' }));
+      images.push(img);
+      return images.length - 1;
+    },
+    '_draw_image': (id, x, y, w, h) => {
+      // images load in the background and show up once they are decoded
+      let img = images[id];
+      if (img.complete) {
+        ctx.drawImage(img, x, y, w, h);
+      }
     },
     '_present': () => {
       ctx.fill();
       ctx.stroke();
-    }
+    },
+    '_ticks': () => performance.now(),
+    '_args_size': () => query_args().length,
+    '_get_args': ptr => {
+      let data = query_args();
+      new Uint8Array(exports.memory.buffer, ptr, data.length).set(data);
+    },
+    '_ws_connect': (url_ptr, url_len) => {
+      socket = new WebSocket(get_string(url_ptr, url_len));
+      socket.onopen = () => {
+        for (const m of socket_pending) socket.send(m);
+        socket_pending = [];
+      };
+      socket.onmessage = e => socket_queue.push(e.data);
+      socket.onclose = () => socket_closed = true;
+    },
+    '_ws_send': (ptr, len) => {
+      let text = get_string(ptr, len);
+      if (socket.readyState === WebSocket.OPEN) {
+        socket.send(text);
+      } else {
+        socket_pending.push(text);
+      }
+    },
+    '_ws_queue_size': () => socket_queue_data().length,
+    '_get_ws_queue': ptr => {
+      let data = socket_queue_data();
+      new Uint8Array(exports.memory.buffer, ptr, data.length).set(data);
+      socket_queue = [];
+    },
+    '_ws_closed': () => socket_closed,
   }
 }).then(x => {
   exports = x.instance.exports;
   exports.init();
 
-  setInterval(() => {
-    exports.main_loop_step();
+  // the game asks to stop by returning false, like after losing the connection
+  const timer = setInterval(() => {
+    if (!exports.main_loop_step()) clearInterval(timer);
   }, 1000 / 60);
 });
 
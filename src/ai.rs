@@ -0,0 +1,258 @@
+//! A computer player.
+//!
+//! With three players a move that is good for one opponent is not
+//! necessarily bad for the other, so there are two ways to search:
+//!
+//! * max-n: every player picks the move that is best for itself, judged by
+//!   a separate score for each player;
+//! * paranoid: the two opponents are assumed to play together against the
+//!   searching player, which turns the game into a two-sided one that alpha-beta
+//!   pruning can cut down.
+//!
+//! Positions are scored by material and mobility. The search deepens one
+//! ply at a time until it reaches the configured depth or runs out of time,
+//! playing the best move of the deepest finished iteration.
+
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::indexed::{IndexedBoard, FIELD_COUNT};
+use crate::moves::Move;
+use crate::piece::{PieceType, Player};
+use crate::rules::GameOutcome;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Strategy {
+    MaxN,
+    #[default] Paranoid,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ai {
+    pub strategy: Strategy,
+    /// Plies to search at most.
+    pub depth: u32,
+    /// Time after which no deeper iteration is searched; the iteration that
+    /// runs out of time is thrown away.
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for Ai {
+    fn default() -> Self {
+        Self {
+            strategy: Strategy::default(),
+            depth: 3,
+            time_budget: Some(Duration::from_secs(2)),
+        }
+    }
+}
+
+/// A score for someone who is mated; far below anything material can reach.
+const MATED: i32 = -1_000_000;
+
+const MOBILITY_WEIGHT: i32 = 2;
+
+pub fn piece_value(typ: PieceType) -> i32 {
+    match typ {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 300,
+        PieceType::Bishop => 320,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 0,
+    }
+}
+
+/// How well each player stands, indexed by `Player`. A player's score is
+/// its own material and mobility minus half of that of both opponents, so
+/// taking a piece from either one of them pays off.
+pub fn evaluate(board: &Board) -> [i32; 3] {
+    let indexed = IndexedBoard::from_board(board);
+    let mut raw = [0; 3];
+    let mut targets = vec![];
+    for field in 0 .. FIELD_COUNT {
+        if let Some(p) = indexed.piece(field) {
+            targets.clear();
+            indexed.pseudo_targets(field, &mut targets);
+            raw[p.player as usize] += piece_value(p.typ) + MOBILITY_WEIGHT * targets.len() as i32;
+        }
+    }
+
+    let total: i32 = raw.iter().sum();
+    let outcome = board.outcome();
+    Player::ALL.map(|p| match outcome {
+        _ if board.is_out(p) => MATED,
+        Some(GameOutcome::Won(winner)) => if winner == p { -MATED } else { MATED },
+        Some(_) => 0,
+        None => {
+            let own = raw[p as usize];
+            own - (total - own) / 2
+        },
+    })
+}
+
+/// Captures first, the most valuable victims leading, which lets alpha-beta
+/// cut more.
+fn ordered_moves(board: &Board) -> Vec<Move> {
+    let mut moves = board.legal_moves(board.current_player());
+    moves.sort_by_key(|m| {
+        let captured = m.captured.map_or(0, |p| piece_value(p.typ) + 1);
+        let promoted = m.promotion.map_or(0, piece_value);
+        -(captured + promoted)
+    });
+    moves
+}
+
+struct Search<'a> {
+    strategy: Strategy,
+    me: Player,
+    deadline: Option<Duration>,
+    now: &'a dyn Fn() -> Duration,
+}
+
+impl Search<'_> {
+    fn out_of_time(&self) -> bool {
+        self.deadline.is_some_and(|d| (self.now)() >= d)
+    }
+
+    /// The scores the position is worth to every player `depth` plies ahead,
+    /// or `None` when time ran out.
+    fn max_n(&self, board: &mut Board, depth: u32) -> Option<[i32; 3]> {
+        if self.out_of_time() {
+            return None;
+        }
+        let moves = ordered_moves(board);
+        if depth == 0 || moves.is_empty() || board.outcome().is_some() {
+            return Some(evaluate(board));
+        }
+
+        let player = board.current_player() as usize;
+        let mut best: Option<[i32; 3]> = None;
+        for mov in moves {
+            board.apply_move_unchecked(mov);
+            let scores = self.max_n(board, depth - 1);
+            board.undo_move();
+
+            let scores = scores?;
+            if best.is_none_or(|b| scores[player] > b[player]) {
+                best = Some(scores);
+            }
+        }
+        best
+    }
+
+    /// The score of the position for `self.me` when both opponents work
+    /// against it, or `None` when time ran out.
+    fn paranoid(&self, board: &mut Board, depth: u32, mut alpha: i32, mut beta: i32) -> Option<i32> {
+        if self.out_of_time() {
+            return None;
+        }
+        let moves = ordered_moves(board);
+        if depth == 0 || moves.is_empty() || board.outcome().is_some() {
+            return Some(evaluate(board)[self.me as usize]);
+        }
+
+        let maximizing = board.current_player() == self.me;
+        let mut best = if maximizing { i32::MIN } else { i32::MAX };
+        for mov in moves {
+            board.apply_move_unchecked(mov);
+            let score = self.paranoid(board, depth - 1, alpha, beta);
+            board.undo_move();
+
+            let score = score?;
+            if maximizing {
+                best = best.max(score);
+                alpha = alpha.max(score);
+            } else {
+                best = best.min(score);
+                beta = beta.min(score);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    /// The best move at `depth`, or `None` when time ran out.
+    fn root(&self, board: &mut Board, moves: &[Move], depth: u32) -> Option<Move> {
+        let mut best: Option<(Move, i32)> = None;
+        for mov in moves {
+            board.apply_move_unchecked(*mov);
+            let score = match self.strategy {
+                Strategy::MaxN => self.max_n(board, depth - 1).map(|s| s[self.me as usize]),
+                Strategy::Paranoid => {
+                    let alpha = best.map_or(i32::MIN, |b| b.1);
+                    self.paranoid(board, depth - 1, alpha, i32::MAX)
+                },
+            };
+            board.undo_move();
+
+            let score = score?;
+            if best.is_none_or(|b| score > b.1) {
+                best = Some((*mov, score));
+            }
+        }
+        best.map(|b| b.0)
+    }
+}
+
+impl Ai {
+    /// Picks a move for the current player, or `None` if it has none.
+    ///
+    /// `now` reads a clock that only has to go forward; it lets the caller
+    /// supply one where `std::time::Instant` is not available.
+    pub fn choose_move(&self, board: &Board, now: &dyn Fn() -> Duration) -> Option<Move> {
+        let mut board = board.clone();
+        let mut moves = ordered_moves(&board);
+        let search = Search {
+            strategy: self.strategy,
+            me: board.current_player(),
+            deadline: self.time_budget.map(|t| now() + t),
+            now,
+        };
+
+        let mut best = *moves.first()?;
+        for depth in 1 ..= self.depth.max(1) {
+            match search.root(&mut board, &moves, depth) {
+                Some(mov) => best = mov,
+                None => break,
+            }
+            // the best move so far is searched first next time
+            let i = moves.iter().position(|m| *m == best).unwrap();
+            moves[..= i].rotate_right(1);
+        }
+        Some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn choose(strategy: Strategy, notation: &str) -> Move {
+        let board = Board::from_notation(notation).unwrap();
+        let ai = Ai { strategy, depth: 2, time_budget: None };
+        let start = Instant::now();
+        ai.choose_move(&board, &|| start.elapsed()).unwrap()
+    }
+
+    #[test]
+    fn takes_hanging_queen() {
+        for strategy in [Strategy::MaxN, Strategy::Paranoid] {
+            let mov = choose(strategy, "3rr3kr/8/8/3qg4/8/8/8/kg7/8/8/8/7ky r - - -");
+            assert_eq!(mov.to, "d4".parse().unwrap(), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn respects_time_budget() {
+        let mut board = Board::new();
+        board.place_pieces();
+        let ai = Ai { depth: 10, time_budget: Some(Duration::ZERO), ..Ai::default() };
+        let start = Instant::now();
+        let mov = ai.choose_move(&board, &|| start.elapsed()).unwrap();
+        assert!(board.legal_moves(Player::Red).contains(&mov));
+    }
+}
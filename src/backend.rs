@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Color(pub u8, pub u8, pub u8);
 
 impl Color {
@@ -9,16 +9,93 @@ impl Color {
     pub const BLACK: Self = Color(0x00, 0x00, 0x00);
 }
 
+/// An image loaded into a backend with `Backend::load_image`, valid for as
+/// long as that backend lives.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ImageHandle(pub usize);
+
+// the terminal backend only produces text input
+#[cfg_attr(feature = "terminal", allow(dead_code))]
 #[derive(PartialEq, Eq, Debug)]
 pub enum MouseButton {
     Left,
     Unknown,
 }
 
+/// A key independent of the backend's key codes. Printable keys are
+/// reported by the character they produce without shift, letters in lower
+/// case.
+#[cfg_attr(feature = "terminal", allow(dead_code))]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Escape,
+    Backspace,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+    Unknown,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+#[cfg_attr(feature = "terminal", allow(dead_code))]
 #[derive(Debug)]
 pub enum Event {
     Quit,
-    MouseButtonUp(MouseButton, i32, i32)
+    MouseButtonDown(MouseButton, i32, i32),
+    MouseButtonUp(MouseButton, i32, i32),
+    MouseMotion(i32, i32),
+    KeyDown(Key, Modifiers),
+    // nothing reacts to releases yet, but backends report them anyway
+    #[allow(dead_code)]
+    KeyUp(Key, Modifiers),
+    /// A line of text typed by the player, without the line break.
+    #[cfg_attr(not(feature = "terminal"), allow(dead_code))]
+    TextInput(String),
+}
+
+/// The horizontal runs `(y, x_from, x_to)` covering the convex polygon with
+/// the corners `points`, ends included.
+pub fn polygon_spans(points: &[(i32, i32)]) -> Vec<(i32, i32, i32)> {
+    let Some(top) = points.iter().map(|p| p.1).min() else { return vec![] };
+    let bottom = points.iter().map(|p| p.1).max().unwrap();
+
+    let mut spans = vec![];
+    for y in top ..= bottom {
+        let mut xs = vec![];
+        for (i, a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            if y < a.1.min(b.1) || y > a.1.max(b.1) {
+                continue;
+            }
+            if a.1 == b.1 {
+                xs.extend([a.0, b.0]);
+            } else {
+                xs.push(a.0 + (b.0 - a.0) * (y - a.1) / (b.1 - a.1));
+            }
+        }
+        if let (Some(from), Some(to)) = (xs.iter().min(), xs.iter().max()) {
+            spans.push((y, *from, *to));
+        }
+    }
+    spans
+}
+
+/// The horizontal runs `(y, x_from, x_to)` covering a circle.
+pub fn circle_spans(center: (i32, i32), radius: i32) -> Vec<(i32, i32, i32)> {
+    (-radius ..= radius).map(|dy| {
+        let dx = ((radius * radius - dy * dy) as f64).sqrt() as i32;
+        (center.1 + dy, center.0 - dx, center.0 + dx)
+    }).collect()
 }
 
 pub trait Backend {
@@ -27,14 +104,32 @@ pub trait Backend {
     fn clear(&mut self);
     fn win_size(&self) -> (u32, u32);
     fn poll_event(&mut self) -> Vec<Event>;
-    fn draw_point(&mut self, x: i32, y: i32);
+    /// Fills the convex polygon with the corners `points`, given in order
+    /// around it.
+    fn fill_polygon(&mut self, points: &[(i32, i32)]) {
+        for (y, from, to) in polygon_spans(points) {
+            self.draw_line((from, y), (to, y));
+        }
+    }
+    fn fill_circle(&mut self, center: (i32, i32), radius: i32) {
+        for (y, from, to) in circle_spans(center, radius) {
+            self.draw_line((from, y), (to, y));
+        }
+    }
     fn text_size(&self, string: &str) -> (u32, u32);
     fn render_text(&mut self, text: &str, x: i32, y: i32, color: Color);
-    fn render_png_data(&mut self, data: &[u8], x: i32, y: i32, w: i32, h: i32);
+    /// Decodes a PNG image once, so it can be drawn cheaply afterwards.
+    fn load_image(&mut self, data: &[u8]) -> ImageHandle;
+    fn draw_image(&mut self, image: ImageHandle, x: i32, y: i32, w: i32, h: i32);
     fn present(&mut self);
+    /// Milliseconds since some fixed point in time, never going backwards.
+    fn ticks(&self) -> u64;
 
     fn read_file(&self, path: &str) -> Vec<u8>;
+    fn write_file(&self, path: &str, data: &[u8]);
 
+    #[cfg_attr(not(target_family = "wasm"), allow(dead_code))]
     fn log(string: String);
+    #[cfg_attr(not(target_family = "wasm"), allow(dead_code))]
     fn elog(string: String);
 }
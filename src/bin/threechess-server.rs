@@ -0,0 +1,117 @@
+//! `threechess-server [<address>] [--websocket <address>] [--position <position>]
+//! [--variant <variant>] [--mate-credit <credit>] [--eliminated <pieces>]
+//! [--clock <time control>]`
+//! runs a game for clients started with `threechess --connect <address>
+//! --seat <seat>`, and for the browser build opened with
+//! `?connect=<websocket address>&seat=<seat>`.
+
+use std::net::TcpListener;
+
+use threechess::{Board, Rules, Server, TimeControl, Variant};
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:7531";
+const DEFAULT_WEBSOCKET_ADDRESS: &str = "0.0.0.0:7532";
+
+fn listen(address: &str) -> TcpListener {
+    match TcpListener::bind(address) {
+        Ok(l) => {
+            println!("listening on {address}");
+            l
+        },
+        Err(e) => {
+            eprintln!("could not listen on {address}: {e}");
+            std::process::exit(1);
+        },
+    }
+}
+
+/// Some number that differs from run to run.
+fn seed() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+struct Options {
+    address: String,
+    websocket_address: String,
+    board: Board,
+    clock: Option<TimeControl>,
+}
+
+/// Reads the command line after the program name.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut address = DEFAULT_ADDRESS.to_string();
+    let mut websocket_address = DEFAULT_WEBSOCKET_ADDRESS.to_string();
+    let mut position = None;
+    let mut rules = Rules::default();
+    let mut clock = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--position" => Board::from_notation(&args.next().unwrap_or_default())
+                .map(|b| position = Some(b))
+                .map_err(|e| format!("invalid position: {e}")),
+            "--variant" => Variant::parse_or_random(&args.next().unwrap_or_default(), seed())
+                .map(|v| rules.variant = v),
+            "--mate-credit" => args.next().unwrap_or_default().parse().map(|c| rules.mate_credit = c),
+            "--eliminated" => args.next().unwrap_or_default().parse()
+                .map(|p| rules.eliminated_pieces = p),
+            "--clock" => args.next().unwrap_or_default().parse().map(|c| clock = Some(c)),
+            "--websocket" => {
+                websocket_address = args.next().unwrap_or_default();
+                Ok(())
+            },
+            _ => {
+                address = arg;
+                Ok(())
+            },
+        }?;
+    }
+    let mut board = position.unwrap_or_else(|| rules.variant.setup());
+    board.set_rules(rules);
+    Ok(Options { address, websocket_address, board, clock })
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1));
+    let Options { address, websocket_address, board, clock } = match options {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        },
+    };
+
+    let listener = listen(&address);
+    let websocket_listener = listen(&websocket_address);
+
+    let server = match clock {
+        Some(control) => Server::with_clock(board, control),
+        None => Server::new(board),
+    };
+    let websocket_server = server.clone();
+    std::thread::spawn(move || websocket_server.serve_websocket(websocket_listener));
+    server.serve(listener);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(String::from)
+    }
+
+    #[test]
+    fn bare_chess960() {
+        let options = parse_args(args("--variant chess960")).unwrap();
+        let Variant::Chess960(n) = options.board.rules().variant else {
+            panic!("{:?}", options.board.rules());
+        };
+        assert_eq!(options.board.to_notation(), Variant::Chess960(n).setup().to_notation());
+
+        let options = parse_args(args("--variant chess960-7 127.0.0.1:1")).unwrap();
+        assert_eq!(options.board.rules().variant, Variant::Chess960(7));
+        assert_eq!(options.address, "127.0.0.1:1");
+        assert!(parse_args(args("--variant chess960-960")).is_err());
+    }
+}
@@ -0,0 +1,1399 @@
+use crate::castling::{Castling, CastlingSide};
+use crate::direction::Direction;
+use crate::indexed::IndexedBoard;
+use crate::moves::{IllegalMove, Move, MoveKind};
+use crate::piece::{Piece, PieceType, Player};
+use crate::rules::{EliminatedPieces, GameOutcome, MateCredit, PlayerStatus, Rules, FIFTY_MOVES,
+                   REPETITIONS};
+use crate::zobrist;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Coord(pub char, pub usize);
+
+impl Coord {
+    /// The files that exist on `rank`, in alphabetical order.
+    pub fn files_of_rank(rank: usize) -> &'static [char] {
+        match rank {
+            1..=4 => &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'],
+            5..=8 => &['a', 'b', 'c', 'd', 'i', 'j', 'k', 'l'],
+            9..=12 => &['e', 'f', 'g', 'h', 'i', 'j', 'k', 'l'],
+            _ => &[],
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        Self::files_of_rank(self.1).contains(&self.0)
+    }
+
+    /// All 96 fields, rank by rank.
+    pub fn all() -> impl Iterator<Item = Coord> {
+        (1..=12).flat_map(|r| Self::files_of_rank(r).iter().map(move |f| Coord(*f, r)))
+    }
+
+    /// The position of a valid coordinate in `Coord::all`, from 0 to 95.
+    pub fn index(&self) -> usize {
+        let files = Self::files_of_rank(self.1);
+        (self.1 - 1) * 8 + files.iter().position(|f| *f == self.0).unwrap()
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        let rank = index / 8 + 1;
+        Coord(Self::files_of_rank(rank)[index % 8], rank)
+    }
+}
+
+impl std::fmt::Display for Coord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.0.to_ascii_uppercase(), self.1)
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    #[default] WHITE,
+    BLACK,
+}
+
+/// The directions rooks move in, and the first half of those of queens
+/// and kings.
+pub(crate) const STRAIGHT_DIRS: [Direction; 9] = [Direction::ForwardRed, Direction::ForwardYellow,
+                                                  Direction::ForwardGreen,
+                                                  Direction::RedRight, Direction::RedLeft,
+                                                  Direction::GreenRight, Direction::GreenLeft,
+                                                  Direction::YellowRight, Direction::YellowLeft,
+];
+
+/// The directions bishops move in.
+pub(crate) const DIAGONAL_DIRS: [Direction; 12] = [Direction::RedYellowToRed, Direction::RedYellowToYellow,
+                                                   Direction::GreenRedToRed, Direction::GreenRedToGreen,
+                                                   Direction::GreenYellowToYellow, Direction::GreenYellowToGreen,
+
+                                                   Direction::RedToRedYellow, Direction::RedToGreenRed,
+                                                   Direction::YellowToRedYellow, Direction::YellowToGreenYellow,
+                                                   Direction::GreenToGreenYellow, Direction::GreenToGreenRed,
+];
+
+/// The directions a pawn of `player` captures in.
+pub(crate) fn pawn_capture_dirs(player: Player) -> [Direction; 6] {
+    match player {
+        Player::Red => [Direction::RedToGreenRed, Direction::RedToRedYellow,
+                        Direction::GreenYellowToGreen, Direction::GreenRedToGreen,
+                        Direction::GreenYellowToYellow, Direction::RedYellowToYellow],
+
+        Player::Green => [Direction::GreenToGreenRed, Direction::GreenToGreenYellow,
+                          Direction::RedYellowToRed, Direction::GreenRedToGreen,
+                          Direction::RedYellowToYellow, Direction::GreenYellowToYellow],
+
+        Player::Yellow => [Direction::YellowToGreenYellow, Direction::YellowToRedYellow,
+                           Direction::GreenRedToGreen, Direction::RedYellowToRed,
+                           Direction::GreenRedToRed, Direction::GreenYellowToGreen],
+    }
+}
+
+/// The rank `player`'s pawns start on, from where they may double step.
+pub(crate) fn pawn_home_rank(player: Player) -> usize {
+    match player {
+        Player::Red => 2,
+        Player::Green => 7,
+        Player::Yellow => 11,
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Field {
+    pub coord: Coord,
+    pub typ: FieldType,
+    pub piece: Option<Piece>,
+}
+
+impl Field {
+    pub fn new(a: char, b: usize, tp: FieldType) -> Self {
+        Self {
+            coord: Coord(a, b),
+            typ: tp,
+            piece: None,
+        }
+    }
+
+    pub(crate) fn get_pawn_dirs(&self, player: Player) -> Vec<Direction> {
+        vec![match player {
+            Player::Red => {
+                if (self.coord.0 >= 'e' && self.coord.0 <= 'h') ||
+                    (self.coord.0 >= 'a' && self.coord.0 <= 'd') {
+                    Direction::ForwardRed
+                } else if self.coord.1 <= 8 {
+                    Direction::ForwardYellow
+                } else {
+                    Direction::ForwardGreen
+                }
+            },
+            Player::Green => {
+                if (self.coord.0 >= 'a' && self.coord.0 <= 'd') ||
+                    (self.coord.0 >= 'i') {
+                    Direction::ForwardGreen
+                } else if self.coord.1 <= 4 {
+                    Direction::ForwardYellow
+                } else {
+                    Direction::ForwardRed
+                }
+            },
+            Player::Yellow => {
+                if (self.coord.0 >= 'e' && self.coord.0 <= 'h') ||
+                    (self.coord.0 >= 'i') {
+                    Direction::ForwardYellow
+                } else if self.coord.1 <= 4 {
+                    Direction::ForwardGreen
+                } else {
+                    Direction::ForwardRed
+                }
+            },
+        }]
+    }
+
+    pub fn get_possible_moves_unchecked(&self, board: &Board) -> Vec<Coord> {
+        let piece = self.piece.unwrap();
+        let player = piece.player;
+        let _fields = board.get_fields();
+
+        let _f = Board::get_fields(board);
+
+
+        let fields = match piece.typ {
+            PieceType::Pawn => {
+                let move_dirs = self.get_pawn_dirs(player);
+
+                let capture_dirs = pawn_capture_dirs(player);
+
+                let is_at_home = self.coord.1 == pawn_home_rank(player);
+
+                let mut fields = vec![];
+                for capture_dir in capture_dirs {
+                    if let Some(a) = capture_dir.next(self, board, &player) {
+                        if let Some(p) = a.piece {
+                            if p.player != player {
+                                fields.push(a.coord);
+                            }
+                        } else if board.en_passant_capture(player, a.coord).is_some()
+                            && ! fields.contains(&a.coord) {
+                                fields.push(a.coord);
+                        }
+                    }
+                }
+
+
+                for move_dir in move_dirs {
+                    if let Some(a) = move_dir.next(self, board, &player) {
+                        if a.piece.is_none() {
+                            fields.push(a.coord);
+
+                            if is_at_home {
+                                if let Some(a) = move_dir.next(&a, board, &player) {
+                                    if a.piece.is_none() {
+                                        fields.push(a.coord);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                fields
+            },
+            PieceType::Rook | PieceType::Bishop |
+            PieceType::Queen => {
+                let dirs = match piece.typ {
+                    PieceType::Rook => STRAIGHT_DIRS.to_vec(),
+                    PieceType::Queen => vec![STRAIGHT_DIRS.to_vec(),
+                                              DIAGONAL_DIRS.to_vec()]
+                        .into_iter().flatten().collect(),
+                    PieceType::Bishop => DIAGONAL_DIRS.to_vec(),
+                    _ => unreachable!("impossible"),
+                };
+
+                let mut fields = vec![];
+                for direction in dirs {
+                    let mut field = Some(*self);
+                    while {field = direction.next(&field.unwrap(), board, &player);
+                            field.is_some()} {
+                        if field.is_none() {
+                            break;
+                        }
+
+                        if let Some(p) = field.unwrap().piece {
+                            if p.player != player {
+                                fields.push(field.unwrap().coord);
+                            }
+                            break;
+                        }
+
+                        fields.push(field.unwrap().coord);
+                    }
+                }
+
+                fields
+            },
+            PieceType::Knight => {
+                let mut fields = vec![];
+
+                // 1 - 2
+                for dir in STRAIGHT_DIRS {
+                    let f1 = dir.next(self, board, &player);
+                    for orth in dir.orthogonals() {
+                        let f3 = f1.and_then(|f| orth.next(&f, board, &player))
+                            .and_then(|f| orth.next(&f, board, &player));
+                        if let Some(f) = f3 {
+                            if let Some(p) = f.piece {
+                                if p.player == player {
+                                    continue;
+                                }
+                            }
+                            if ! fields.contains(&f.coord) {
+                                fields.push(f.coord);
+                            }
+                        }
+                    }
+                }
+
+                // 2 - 1
+                for dir in STRAIGHT_DIRS {
+                    let f2 = dir.next(self, board, &player)
+                        .and_then(|f| dir.next(&f, board, &player));
+                    for orth in dir.orthogonals() {
+                        let f3 = f2.and_then(|f| orth.next(&f, board, &player));
+                        if let Some(f) = f3 {
+                            if let Some(p) = f.piece {
+                                if p.player == player {
+                                    continue;
+                                }
+                            }
+                            if ! fields.contains(&f.coord) {
+                                fields.push(f.coord);
+                            }
+                        }
+                    }
+                }
+                fields
+            }
+            PieceType::King => {
+                let mut dirs = STRAIGHT_DIRS.to_vec();
+                dirs.append(&mut DIAGONAL_DIRS.to_vec());
+                let mut fields = vec![];
+                for d in dirs {
+                    let field = d.next(self, board, &player);
+                    if let Some(f) = field {
+                        if let Some(p) = f.piece {
+                            if p.player == player {
+                                continue;
+                            }
+                        }
+                        fields.push(f.coord);
+                    }
+                }
+                fields
+            },
+        };
+
+        // directions meeting again behind a seam can lead to the same field
+        let mut unique = vec![];
+        for f in fields {
+            if ! unique.contains(&f) {
+                unique.push(f);
+            }
+        }
+        unique
+    }
+
+    pub fn get_possible_moves_unking(&self, board: &Board) -> (Vec<Coord>, Vec<Coord>) {
+        let moves = self.get_possible_moves_unchecked(board)
+            .into_iter().filter(|x| {
+                let mut new_board = board.clone();
+                new_board.get_field_mut(x.0, x.1)
+                    .unwrap().piece =
+                    self.piece;
+
+                new_board.get_field_mut(self.coord.0, self.coord.1)
+                    .unwrap().piece =
+                    None;
+
+                if self.piece.unwrap().typ == PieceType::Pawn {
+                    if let Some(c) = board.en_passant_capture(self.piece.unwrap().player, *x) {
+                        new_board.get_field_mut(c.0, c.1).unwrap().piece = None;
+                    }
+                }
+
+                ! new_board.is_check(self.piece.unwrap().player)
+        }).collect::<Vec<_>>();
+
+        let mut king_capt_moves = vec![];
+        for mov in &moves {
+            let f = board.get_field(mov.0, mov.1).unwrap();
+            if let Some(p) = f.piece {
+                if p.player != self.piece.unwrap().player &&
+                    p.typ == PieceType::King {
+                        king_capt_moves.push(*mov);
+                }
+            }
+        }
+
+        (moves, king_capt_moves)
+    }
+
+    fn get_castling_moves(&self, board: &Board) -> Vec<Coord> {
+        let piece = self.piece.unwrap();
+        if piece.typ != PieceType::King {
+            return vec![];
+        }
+
+        Castling::of(piece.player).iter()
+            .filter(|c| c.king_from == self.coord && board.can_castle(piece.player, c))
+            .map(|c| c.king_to)
+            .collect()
+    }
+
+    pub fn get_possible_moves(&self, board: &Board) -> Vec<Coord> {
+        let (moves, king_capt_moves) = self.get_possible_moves_unking(board);
+        if king_capt_moves.is_empty() && self.piece.unwrap().player.can_capture_king(board) {
+            vec![]
+        } else if king_capt_moves.is_empty() {
+            let mut moves = moves;
+            moves.append(&mut self.get_castling_moves(board));
+            moves
+        } else {
+            king_capt_moves
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Section {
+    pub fields: [[Field; 4]; 4],
+    pub start_file: char,
+    pub start_rank: usize,
+    points: fn (i32, i32) -> [(i32,i32); 4],
+}
+
+pub(crate) fn cadd(c: char, i: usize) -> char {
+    (c as u8 + i as u8) as char
+}
+
+pub(crate) fn csub(c: char, i: usize) -> char {
+    (c as u8 - i as u8) as char
+}
+
+impl Section {
+    pub fn new(f: char, r: usize, inverse_colors: bool,
+               pts: fn(i32,i32)->[(i32,i32);4]) -> Self {
+        let tp1 = if inverse_colors { FieldType::BLACK  }
+        else { FieldType::WHITE };
+
+        let tp2 = if ! inverse_colors { FieldType::BLACK  }
+        else { FieldType::WHITE };
+
+        Self {
+            points: pts,
+            fields: [[
+                Field::new(f, r, tp2),
+                Field::new(f, r+1, tp1),
+                Field::new(f, r+2, tp2),
+                Field::new(f, r+3, tp1),
+            ],[
+                Field::new(cadd(f, 1), r, tp1),
+                Field::new(cadd(f, 1), r+1, tp2),
+                Field::new(cadd(f, 1), r+2, tp1),
+                Field::new(cadd(f, 1), r+3, tp2),
+            ],[
+                Field::new(cadd(f, 2), r, tp2),
+                Field::new(cadd(f, 2), r+1, tp1),
+                Field::new(cadd(f, 2), r+2, tp2),
+                Field::new(cadd(f, 2), r+3, tp1),
+            ],[
+                Field::new(cadd(f, 3), r, tp1),
+                Field::new(cadd(f, 3), r+1, tp2),
+                Field::new(cadd(f, 3), r+2, tp1),
+                Field::new(cadd(f, 3), r+3, tp2),
+            ]],
+            start_file: f,
+            start_rank: r,
+        }
+    }
+
+    pub fn get_radius_and_height(&self, ww: i32, wh: i32) -> (i32, i32) {
+        let radius: i32 = ((ww.min(wh) / 2) as f32 * 0.9) as _;
+        let height: i32 = ((3f32.sqrt() * radius as f32) / 2.0) as i32;
+
+        (radius, height)
+    }
+
+    pub fn get_coords(&self, x: usize, y: usize, ww: i32, mut wh: i32) -> [(i32, i32); 4] {
+        let (radius, height) = self.get_radius_and_height(ww, wh);
+
+
+        let offx = (ww - 2 * radius) / 2;
+        let offy = (wh - 2 * height) / 2;
+
+//        ww -= offx;
+        wh -= offy;
+
+        let pts = (self.points)(radius, height);
+
+        let y0 = wh - pts[0].1;
+        let y1 = wh - pts[1].1;
+        let y2 = wh - pts[2].1;
+        let y3 = wh - pts[3].1;
+
+        let x0 = offx + pts[0].0;
+        let x1 = offx + pts[1].0;
+        let x2 = offx + pts[2].0;
+        let x3 = offx + pts[3].0;
+        let mut coords = [(0, 0); 4];
+
+        for a in 0 .. 2 {
+            for b in 0 .. 2 {
+                let xfrac = (x + a) as f32 / 4.0;
+                let yfrac = (y + b) as f32 / 4.0;
+
+                let xo0 = x0 as f32 + (x1 - x0) as f32 * xfrac;
+                let xo1 = x3 as f32 + (x2 - x3) as f32 * xfrac;
+                let rx = xo0 + (xo1 - xo0) * yfrac;
+
+                let yo0 = y0 as f32 + (y3 - y0) as f32 * yfrac;
+                let yo1 = y1 as f32 + (y2 - y1) as f32 * yfrac;
+                let ry = yo0 + (yo1 - yo0) * xfrac;
+
+                coords[2 * a + b] = (rx as i32, ry as i32);
+            }
+        }
+
+        coords
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Board {
+    sections: [Section; 6],
+    current_player: Player,
+    /// The players who are out, in the order they left.
+    out: Vec<Departure>,
+    rules: Rules,
+    castling: [[bool; 2]; 3],
+    en_passant: [Option<EnPassant>; 3],
+    /// Moves since the last capture or pawn move.
+    halfmoves: usize,
+    history: Vec<HistoryEntry>,
+}
+
+/// A player leaving the game, with its pieces as they were before the
+/// rules for eliminated pieces removed or took them over.
+#[derive(Debug, Clone)]
+struct Departure {
+    player: Player,
+    status: PlayerStatus,
+    pieces: Vec<(Coord, Piece)>,
+}
+
+/// The field a pawn skipped with its double step, and where it landed.
+///
+/// In a three player game the skipped field stays open for en passant until
+/// the pawn's owner moves again, so both opponents get one chance to take,
+/// not just the player directly after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnPassant {
+    pub target: Coord,
+    pub pawn: Coord,
+}
+
+/// What `undo_move` needs besides the move itself to restore a position.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    mov: Move,
+    current_player: Player,
+    /// How many players were out; later ones come back.
+    out: usize,
+    castling: [[bool; 2]; 3],
+    en_passant: [Option<EnPassant>; 3],
+    halfmoves: usize,
+    /// The hash of the position before the move.
+    hash: u64,
+}
+
+/// Whether a pawn of `player` arriving at `coord` has reached a far end.
+fn is_promotion_field(player: Player, coord: Coord) -> bool {
+    match player {
+        Player::Red => coord.1 == 8 || coord.1 == 12,
+        Player::Green => coord.1 == 1 || coord.1 == 12,
+        Player::Yellow => coord.1 == 8 || coord.1 == 1,
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Board {
+    pub fn new() -> Self {
+        let s = [
+            Section::new('a', 1, false,
+            |r, h| [(r/2, 0), (r, 0), (r, h), (r/4, h/2)]),
+            Section::new('e', 1, false,
+            |r, h| [(r, 0), (r+r/2, 0), (r+3*r/4, h/2), (r, h)]),
+            Section::new('e', 9, false,
+            |r, h| [(r, h), (r+3*r/4, h/2), (r*2, h), (r+3*r/4, h+h/2)]),
+            Section::new('i', 9, true,
+            |r, h| [(r, h), (r, h*2),(r+r/2, h*2), (r+3*r/4, h+h/2)]),
+            Section::new('i', 5, false,
+            |r, h| [(r, h), (r, h*2), (r/2, h*2), (r/4, h+h/2)]),
+            Section::new('a', 5, false,
+            |r, h| [(r/4, h/2), (r, h), (r/4, h+h/2), (0, h)]),
+        ];
+        Board {
+            sections: s,
+            current_player: Player::Red,
+            out: vec![],
+            rules: Rules::default(),
+            castling: [[true; 2]; 3],
+            en_passant: [None; 3],
+            halfmoves: 0,
+            history: vec![],
+        }
+    }
+
+    pub fn sections(&self) -> &[Section; 6] {
+        &self.sections
+    }
+
+    pub fn current_player(&self) -> Player {
+        self.current_player
+    }
+
+    pub fn set_current_player(&mut self, player: Player) {
+        self.current_player = player;
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    pub fn status(&self, player: Player) -> PlayerStatus {
+        self.out.iter().find(|d| d.player == player)
+            .map_or(PlayerStatus::Playing, |d| d.status)
+    }
+
+    /// Whether `player` has dropped out of the turn order.
+    pub fn is_out(&self, player: Player) -> bool {
+        self.status(player).is_out()
+    }
+
+    /// The players who are out or drawn, in the order they left.
+    pub fn departures(&self) -> impl Iterator<Item = (Player, PlayerStatus)> + '_ {
+        self.out.iter().map(|d| (d.player, d.status))
+    }
+
+    /// How many of the `departures` came before the move at `index` in the
+    /// history, all of them for an index past its end.
+    pub fn departures_before(&self, index: usize) -> usize {
+        self.history.get(index).map_or(self.out.len(), |e| e.out)
+    }
+
+    /// Marks `player`, who must still be playing, with `status`, applying
+    /// the rules for its pieces when it is out.
+    pub fn set_status(&mut self, player: Player, status: PlayerStatus) {
+        let taker = match status {
+            PlayerStatus::Checkmated { by } if self.rules.eliminated_pieces == EliminatedPieces::Captured => by,
+            _ => None,
+        };
+
+        let mut pieces = vec![];
+        if status.is_out() && self.rules.eliminated_pieces != EliminatedPieces::Neutral {
+            for coord in Coord::all() {
+                let field = self.get_field_mut(coord.0, coord.1).unwrap();
+                if let Some(piece) = field.piece.filter(|p| p.player == player) {
+                    // a second king would leave the taker two to guard
+                    field.piece = taker.filter(|_| piece.typ != PieceType::King)
+                        .map(|t| Piece { player: t, ..piece });
+                    pieces.push((coord, piece));
+                }
+            }
+        }
+        if status.is_out() {
+            self.en_passant[player as usize] = None;
+        }
+        self.out.push(Departure { player, status, pieces });
+    }
+
+    /// The last player standing, once the other two are out.
+    pub fn winner(&self) -> Option<Player> {
+        let alive: Vec<_> = Player::ALL.into_iter()
+            .filter(|p| self.status(*p) == PlayerStatus::Playing)
+            .collect();
+
+        if alive.len() == 1 {
+            Some(alive[0])
+        } else {
+            None
+        }
+    }
+
+    /// The player whose king stands on the variant's hill, if any.
+    fn king_on_hill(&self) -> Option<Player> {
+        self.rules.variant.hill().iter()
+            .filter_map(|c| self.get_field(c.0, c.1).unwrap().piece)
+            .find(|p| p.typ == PieceType::King && self.status(p.player) == PlayerStatus::Playing)
+            .map(|p| p.player)
+    }
+
+    /// How the game ended, or `None` while it goes on.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if let Some(winner) = self.winner().or_else(|| self.king_on_hill()) {
+            Some(GameOutcome::Won(winner))
+        } else if Player::ALL.into_iter().any(|p| self.status(p) == PlayerStatus::Drawn) {
+            Some(GameOutcome::DrawAgreed)
+        } else if self.repetitions() >= REPETITIONS {
+            Some(GameOutcome::Repetition)
+        } else if self.halfmoves >= FIFTY_MOVES {
+            Some(GameOutcome::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    /// The place of every player, best first: the winner, then those still
+    /// in sharing a place, then the others in the reverse order they left
+    /// in.
+    pub fn ranking(&self) -> Vec<(usize, Player)> {
+        let winner = match self.outcome() {
+            Some(GameOutcome::Won(w)) => Some(w),
+            _ => None,
+        };
+        let mut ranking: Vec<_> = winner.map(|w| (1, w)).into_iter().collect();
+        let place = ranking.len() + 1;
+        ranking.extend(Player::ALL.into_iter()
+                       .filter(|p| ! self.is_out(*p) && Some(*p) != winner)
+                       .map(|p| (place, p)));
+
+        let mut place = ranking.len();
+        for d in self.out.iter().rev().filter(|d| d.status.is_out()) {
+            place += 1;
+            ranking.push((place, d.player));
+        }
+        ranking
+    }
+
+    /// The Zobrist hash of the position, see `zobrist`.
+    pub fn hash(&self) -> u64 {
+        zobrist::hash(self)
+    }
+
+    /// How often the current position has come up in the moves played,
+    /// counting this time.
+    pub fn repetitions(&self) -> usize {
+        // nothing before the last capture or pawn move can come back
+        let hash = self.hash();
+        1 + self.history.iter().rev().take(self.halfmoves)
+            .filter(|e| e.hash == hash)
+            .count()
+    }
+
+    /// The moves played since the last capture or pawn move, for the
+    /// fifty-move rule.
+    pub fn halfmoves(&self) -> usize {
+        self.halfmoves
+    }
+
+    pub fn set_halfmoves(&mut self, halfmoves: usize) {
+        self.halfmoves = halfmoves;
+    }
+
+    /// Ends the game in a draw among the players still in.
+    pub fn agree_draw(&mut self) {
+        for player in Player::ALL {
+            if self.status(player) == PlayerStatus::Playing {
+                self.set_status(player, PlayerStatus::Drawn);
+            }
+        }
+    }
+
+    /// Whether `player` still has the right to castle to `side`, i.e.
+    /// neither the king nor that rook has moved or been captured.
+    pub fn has_castling_right(&self, player: Player, side: CastlingSide) -> bool {
+        self.castling[player as usize][side as usize]
+    }
+
+    pub fn set_castling_right(&mut self, player: Player, side: CastlingSide, right: bool) {
+        self.castling[player as usize][side as usize] = right;
+    }
+
+    /// The en passant opportunity left by `player`'s last double step.
+    pub fn en_passant(&self, player: Player) -> Option<EnPassant> {
+        self.en_passant[player as usize]
+    }
+
+    pub fn set_en_passant(&mut self, player: Player, en_passant: Option<EnPassant>) {
+        self.en_passant[player as usize] = en_passant;
+    }
+
+    /// The field a pawn of `player` standing on `coord` moves to next.
+    pub fn pawn_step(&self, player: Player, coord: Coord) -> Option<Coord> {
+        let field = self.get_field(coord.0, coord.1)?;
+        let dir = field.get_pawn_dirs(player)[0];
+        dir.next(field, self, &player).map(|f| f.coord)
+    }
+
+    /// If a pawn of `player` moving onto the empty field `to` captures en
+    /// passant, the field of the pawn it takes.
+    pub fn en_passant_capture(&self, player: Player, to: Coord) -> Option<Coord> {
+        if ! self.rules.variant.en_passant() {
+            return None;
+        }
+        Player::ALL.into_iter()
+            .filter(|p| *p != player)
+            .filter_map(|p| self.en_passant[p as usize].map(|e| (p, e)))
+            .find(|(p, e)| e.target == to && self.get_field(e.pawn.0, e.pawn.1)
+                  .and_then(|f| f.piece)
+                  .is_some_and(|x| x.player == *p && x.typ == PieceType::Pawn))
+            .map(|(_, e)| e.pawn)
+    }
+
+    /// Whether `player` may castle as described by `castling` right now.
+    pub fn can_castle(&self, player: Player, castling: &Castling) -> bool {
+        IndexedBoard::from_board(self).can_castle(player, castling)
+    }
+
+    /// Legal target fields of the piece standing on `coord`.
+    pub fn get_possible_moves(&self, coord: Coord) -> Vec<Coord> {
+        match self.get_field(coord.0, coord.1) {
+            Some(f) if f.piece.is_some() => IndexedBoard::from_board(self)
+                .legal_targets(coord.index()).into_iter()
+                .map(Coord::from_index)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// All legal moves of `player` in the current position.
+    pub fn legal_moves(&self, player: Player) -> Vec<Move> {
+        let mut moves = vec![];
+        for (from, targets) in IndexedBoard::from_board(self).all_legal_targets(player) {
+            let from = Coord::from_index(from);
+            let piece = self.get_field(from.0, from.1).unwrap().piece.unwrap();
+
+            for to in targets.into_iter().map(Coord::from_index) {
+                let captured = self.get_field(to.0, to.1).unwrap().piece;
+                let kind = match piece.typ {
+                    PieceType::King => Castling::of(player).iter()
+                        .find(|c| c.king_from == from && c.king_to == to)
+                        .map_or(MoveKind::Normal, |c| MoveKind::Castling(c.side)),
+                    PieceType::Pawn if captured.is_none() => self.en_passant_capture(player, to)
+                        .map_or(MoveKind::Normal, MoveKind::EnPassant),
+                    _ => MoveKind::Normal,
+                };
+                let captured = match kind {
+                    MoveKind::EnPassant(c) => self.get_field(c.0, c.1).unwrap().piece,
+                    _ => captured,
+                };
+
+                let mov = Move {
+                    from,
+                    to,
+                    piece,
+                    captured,
+                    promotion: None,
+                    kind,
+                };
+
+                if piece.typ == PieceType::Pawn && is_promotion_field(player, to) {
+                    for &typ in self.rules.variant.promotions() {
+                        moves.push(Move {
+                            promotion: Some(typ),
+                            ..mov
+                        });
+                    }
+                } else {
+                    moves.push(mov);
+                }
+            }
+        }
+        moves
+    }
+
+    /// The legal moves of the current player from `from` to `to`. There is
+    /// more than one only when a pawn promotes, one for each choice of piece.
+    pub fn find_moves(&self, from: Coord, to: Coord) -> Vec<Move> {
+        self.legal_moves(self.current_player).into_iter()
+            .filter(|m| m.from == from && m.to == to)
+            .collect()
+    }
+
+    /// The legal move of the current player from `from` to `to` promoting to
+    /// `promotion`, which has to be `None` exactly when no pawn promotes.
+    pub fn find_move(&self, from: Coord, to: Coord, promotion: Option<PieceType>) -> Option<Move> {
+        self.find_moves(from, to).into_iter()
+            .find(|m| m.promotion == promotion)
+    }
+
+    /// Plays `mov` for the current player and passes the turn on, skipping
+    /// and marking players that are mated. The board is left untouched if
+    /// the move is not legal.
+    pub fn apply_move(&mut self, mov: Move) -> Result<(), IllegalMove> {
+        if mov.piece.player != self.current_player
+            || ! self.legal_moves(self.current_player).contains(&mov) {
+                return Err(IllegalMove(mov));
+            }
+
+        self.apply_move_unchecked(mov);
+        Ok(())
+    }
+
+    /// Like `apply_move`, but trusts the caller that `mov` was taken from
+    /// `legal_moves` of the current player.
+    pub fn apply_move_unchecked(&mut self, mov: Move) {
+        self.history.push(HistoryEntry {
+            mov,
+            current_player: self.current_player,
+            out: self.out.len(),
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmoves: self.halfmoves,
+            hash: self.hash(),
+        });
+
+        if mov.piece.typ == PieceType::Pawn || mov.captured.is_some() {
+            self.halfmoves = 0;
+        } else {
+            self.halfmoves += 1;
+        }
+
+        let player = mov.piece.player;
+        self.en_passant[player as usize] = None;
+        if mov.piece.typ == PieceType::Pawn && self.rules.variant.en_passant() {
+            let from = *self.get_field(mov.from.0, mov.from.1).unwrap();
+            let dir = from.get_pawn_dirs(player)[0];
+            let skipped = dir.next(&from, self, &player);
+            if let Some(skipped) = skipped {
+                if dir.next(&skipped, self, &player).is_some_and(|f| f.coord == mov.to) {
+                    self.en_passant[player as usize] = Some(EnPassant {
+                        target: skipped.coord,
+                        pawn: mov.to,
+                    });
+                }
+            }
+        }
+
+        let mut piece = mov.piece;
+        if let Some(typ) = mov.promotion {
+            piece.typ = typ;
+        }
+
+        self.get_field_mut(mov.to.0, mov.to.1).unwrap().piece = Some(piece);
+        self.get_field_mut(mov.from.0, mov.from.1).unwrap().piece = None;
+
+        match mov.kind {
+            MoveKind::Castling(side) => {
+                let c = Castling::of(mov.piece.player)[side as usize];
+                let rook = self.get_field(c.rook_from.0, c.rook_from.1).unwrap().piece;
+                self.get_field_mut(c.rook_to.0, c.rook_to.1).unwrap().piece = rook;
+                self.get_field_mut(c.rook_from.0, c.rook_from.1).unwrap().piece = None;
+            },
+            MoveKind::EnPassant(c) => {
+                self.get_field_mut(c.0, c.1).unwrap().piece = None;
+            },
+            MoveKind::Normal => (),
+        }
+
+        // a pawn taken after its double step no longer offers en passant
+        if mov.captured.is_some() {
+            let taken = match mov.kind {
+                MoveKind::EnPassant(c) => c,
+                _ => mov.to,
+            };
+            for e in &mut self.en_passant {
+                if e.is_some_and(|e| e.pawn == taken) {
+                    *e = None;
+                }
+            }
+        }
+
+        if mov.piece.typ == PieceType::King {
+            self.castling[mov.piece.player as usize] = [false; 2];
+        }
+
+        // a rook leaving or being captured on its home field loses its right
+        for player in Player::ALL {
+            for c in Castling::of(player) {
+                if c.rook_from == mov.from || c.rook_from == mov.to {
+                    self.set_castling_right(player, c.side, false);
+                }
+            }
+        }
+
+        // a player losing its king is out as if mated by the taker
+        if let Some(king) = mov.captured.filter(|p| p.typ == PieceType::King) {
+            if ! self.is_out(king.player) {
+                self.set_status(king.player, PlayerStatus::Checkmated { by: Some(player) });
+            }
+        }
+
+        self.pass_turn();
+    }
+
+    /// Gives the turn to the next player still in the game, marking players
+    /// that cannot move as mated or stalemated and going on.
+    fn pass_turn(&mut self) {
+        let mover = self.current_player;
+        loop {
+            if let Some(winner) = self.winner().or_else(|| self.king_on_hill()) {
+                self.current_player = winner;
+                return;
+            }
+            self.current_player = self.current_player.next();
+            let player = self.current_player;
+            if self.is_out(player) {
+                continue;
+            }
+            if ! player.is_mate(self) {
+                return;
+            }
+
+            let status = if self.is_check(player) {
+                PlayerStatus::Checkmated { by: Some(self.mate_credit(player, mover)) }
+            } else {
+                PlayerStatus::Stalemated
+            };
+            self.set_status(player, status);
+        }
+    }
+
+    /// The player credited with mating `player` after `mover` moved.
+    fn mate_credit(&self, player: Player, mover: Player) -> Player {
+        let indexed = IndexedBoard::from_board(self);
+        match self.rules.mate_credit {
+            MateCredit::Checker if ! indexed.is_check_by(player, mover) => Player::ALL.into_iter()
+                .find(|p| *p != player && indexed.is_check_by(player, *p))
+                .unwrap_or(mover),
+            _ => mover,
+        }
+    }
+
+    /// Drops `player` out of the game with `status`, for example when it
+    /// resigns or its time has run out. Taking back a move played before
+    /// brings it back.
+    pub fn eliminate(&mut self, player: Player, status: PlayerStatus) {
+        self.set_status(player, status);
+        if self.current_player == player {
+            self.pass_turn();
+        }
+    }
+
+    /// Takes back the last applied move, returning it.
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let entry = self.history.pop()?;
+        let mov = entry.mov;
+
+        while self.out.len() > entry.out {
+            for (coord, piece) in self.out.pop().unwrap().pieces {
+                self.get_field_mut(coord.0, coord.1).unwrap().piece = Some(piece);
+            }
+        }
+
+        self.get_field_mut(mov.from.0, mov.from.1).unwrap().piece = Some(mov.piece);
+
+        match mov.kind {
+            MoveKind::Castling(side) => {
+                self.get_field_mut(mov.to.0, mov.to.1).unwrap().piece = None;
+
+                let c = Castling::of(mov.piece.player)[side as usize];
+                let rook = self.get_field(c.rook_to.0, c.rook_to.1).unwrap().piece;
+                self.get_field_mut(c.rook_from.0, c.rook_from.1).unwrap().piece = rook;
+                self.get_field_mut(c.rook_to.0, c.rook_to.1).unwrap().piece = None;
+            },
+            MoveKind::EnPassant(c) => {
+                self.get_field_mut(mov.to.0, mov.to.1).unwrap().piece = None;
+                self.get_field_mut(c.0, c.1).unwrap().piece = mov.captured;
+            },
+            MoveKind::Normal => {
+                self.get_field_mut(mov.to.0, mov.to.1).unwrap().piece = mov.captured;
+            },
+        }
+
+        self.current_player = entry.current_player;
+        self.castling = entry.castling;
+        self.en_passant = entry.en_passant;
+        self.halfmoves = entry.halfmoves;
+        Some(mov)
+    }
+
+    /// The moves played so far, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &Move> {
+        self.history.iter().map(|e| &e.mov)
+    }
+
+    pub fn get_fields(&self) -> Vec<Field> {
+        self.sections.iter().flat_map(|x| x.fields
+                         .into_iter()
+                         .collect::<Vec<_>>()
+                         ).flatten()
+                          .collect::<Vec<_>>()
+    }
+
+    pub fn get_possible_move_targets_unchecked(&self, _player: Player) -> Vec<Coord> {
+        let fields = self.get_fields();
+        let mut targets = vec![];
+        for f in fields {
+            if f.piece.is_none() {
+                continue;
+            }
+            let mut moves = f.get_possible_moves_unchecked(self);
+            targets.append(&mut moves);
+        }
+        targets
+    }
+
+    pub fn get_king_field(&self, player: Player) -> Option<Field> {
+        let fields = self.get_fields();
+        for f in fields {
+            if let Some(p) = f.piece {
+                if p.player == player && p.typ == PieceType::King {
+                    return Some(f);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_check(&self, player: Player) -> bool {
+        IndexedBoard::from_board(self).is_check(player)
+    }
+
+    fn get_section_and_field(&self, file: char, rank: usize) -> Option<(Section, &Field)> {
+        for s in &self.sections {
+            let sect = *s;
+            for r in &s.fields {
+                for f in r.iter() {
+                    if f.coord.0 == file
+                        && f.coord.1 == rank {
+                            return Some((sect, f));
+                        }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn get_section_and_field_mut(&mut self, file: char, rank: usize) -> Option<(Section, &mut Field)> {
+        for s in &mut self.sections {
+            let sect = *s;
+            for r in &mut s.fields {
+                for f in r.iter_mut() {
+                    if f.coord.0 == file
+                        && f.coord.1 == rank {
+                            return Some((sect, f));
+                        }
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn get_field(&self, file: char, rank: usize) -> Option<&Field> {
+        self.get_section_and_field(file, rank).map(|(_, f)| f)
+    }
+
+    pub fn get_field_mut(&mut self, file: char, rank: usize) -> Option<&mut Field> {
+        self.get_section_and_field_mut(file, rank).map(|(_, f)| f)
+    }
+
+    pub fn get_coords(&self, coord: Coord, ww: i32, wh: i32) -> Option<[(i32, i32); 4]> {
+        let (sect, _) = self.get_section_and_field(coord.0, coord.1)?;
+
+        for a in 0 .. 4 {
+            for b in 0 .. 4 {
+                if sect.fields[a][b].coord == coord {
+                    return Some(sect.get_coords(a, b, ww, wh));
+                }
+            }
+        }
+        None
+    }
+
+    fn place_pieces_half(&mut self, rank: usize,
+                         start_file: char, mut invf: bool, invr: bool,
+                         right: bool, player: Player) {
+
+        if right {
+            invf ^= true;
+        }
+
+        self.get_field_mut(cadd(start_file,
+                            if invf {3} else {0}), rank).unwrap()
+                                .piece = Some(Piece {
+                                    typ: PieceType::Rook,
+                                    player,
+                                });
+
+        self.get_field_mut(cadd(start_file, if invf {0} else {3}), rank).unwrap()
+                                .piece = Some(Piece {
+                                    typ: if right {PieceType::King} else {PieceType::Queen},
+                                    player,
+                                });
+
+        self.get_field_mut(cadd(start_file, if invf {2} else {1}), rank).unwrap()
+                                .piece = Some(Piece {
+                                    typ: PieceType::Knight,
+                                    player,
+                                });
+        self.get_field_mut(cadd(start_file, if invf {1} else {2}), rank).unwrap()
+                                .piece = Some(Piece {
+                                    typ: PieceType::Bishop,
+                                    player,
+                                });
+
+        for a in 0 .. 4 {
+            self.get_field_mut(cadd(start_file,
+                a), if invr {rank-1} else {rank+1}) .unwrap()
+                        .piece = Some(Piece {
+                            typ: PieceType::Pawn,
+                            player,
+                        });
+        }
+
+    }
+
+    pub fn place_pieces(&mut self) {
+        self.place_pieces_half( 1, 'a', false, false, false, Player::Red);
+        self.place_pieces_half( 1, 'e', false, false, true,  Player::Red);
+
+        self.place_pieces_half( 8, 'i', true,  true, false, Player::Green);
+        self.place_pieces_half( 8, 'a', true,  true, true, Player::Green);
+
+        self.place_pieces_half(12, 'e', true,  true, false, Player::Yellow);
+        self.place_pieces_half(12, 'i', false,  true, true,  Player::Yellow);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::COLOR_LETTERS;
+
+    #[test]
+    fn castling_on_every_home_rank() {
+        for player in Player::ALL {
+            let notation = format!("rr3kr2rr/8/8/8/8/8/8/rg2kg3rg/8/8/8/3ryky2ry {} - rKQgKQyKQ -",
+                                   COLOR_LETTERS[player as usize]);
+            let board = Board::from_notation(&notation).unwrap();
+            for c in Castling::of(player) {
+                let mut board = board.clone();
+                let mov = board.find_move(c.king_from, c.king_to, None).unwrap();
+                assert_eq!(mov.kind, MoveKind::Castling(c.side));
+                board.apply_move(mov).unwrap();
+
+                let piece = |c: Coord| board.get_field(c.0, c.1).unwrap().piece;
+                assert_eq!(piece(c.king_to), Some(Piece { typ: PieceType::King, player }));
+                assert_eq!(piece(c.rook_to), Some(Piece { typ: PieceType::Rook, player }));
+                assert_eq!((piece(c.king_from), piece(c.rook_from)), (None, None));
+                assert!(! board.has_castling_right(player, CastlingSide::King));
+                assert!(! board.has_castling_right(player, CastlingSide::Queen));
+
+                board.undo_move();
+                assert_eq!(board.to_notation(), notation);
+            }
+        }
+    }
+
+    #[test]
+    fn no_castling_through_check() {
+        // the red rook on i5 covers i8, which green's king crosses going to j8
+        let board = Board::from_notation("rr3kr2rr/8/8/8/4rr3/8/8/rg2kg3rg/8/8/8/3ryky2ry g - rKQgKQyKQ -")
+            .unwrap();
+        assert!(board.parse_move("O-O").is_ok());
+        assert!(board.parse_move("O-O-O").is_err());
+
+        // nor out of check
+        let board = Board::from_notation("rr3kr2rr/8/8/4ry3/8/8/8/rg2kg3rg/8/8/8/3ryky2ry r - rKQgKQyKQ -")
+            .unwrap();
+        assert!(board.is_check(Player::Red));
+        assert!(board.parse_move("O-O").is_err());
+        assert!(board.parse_move("O-O-O").is_err());
+
+        // nor with a rook that went away and came back
+        let mut board = Board::from_notation("rr3kr2rr/8/8/8/8/8/8/rg2kg3rg/8/8/8/3ryky2ry g - rKQgKQyKQ -")
+            .unwrap();
+        for s in ["Rb8", "Ra8"] {
+            board.apply_move(board.parse_move(s).unwrap()).unwrap();
+            board.set_current_player(Player::Green);
+        }
+        assert!(board.parse_move("O-O").is_err());
+        assert!(board.parse_move("O-O-O").is_ok());
+    }
+
+    #[test]
+    fn en_passant_by_both_followers() {
+        // green waits on f4 and yellow on d4 for red's e-pawn
+        let mut board = Board::from_notation("4kr3/4pr3/8/3py1pg2/8/8/8/kg7/8/8/8/7ky r - - -").unwrap();
+        board.apply_move(board.parse_move("e4").unwrap()).unwrap();
+        let skipped = board.to_notation();
+        assert!(skipped.ends_with(" re3"));
+
+        let mut green = board.clone();
+        let mov = green.parse_move("fxe3").unwrap();
+        assert_eq!(mov.kind, MoveKind::EnPassant(Coord('e', 4)));
+        green.apply_move(mov).unwrap();
+        assert!(green.get_field('e', 4).unwrap().piece.is_none());
+        assert_eq!(green.en_passant(Player::Red), None);
+        green.undo_move();
+        assert_eq!(green.to_notation(), skipped);
+
+        board.apply_move(board.parse_move("Kb8").unwrap()).unwrap();
+        let mut yellow = board.clone();
+        yellow.apply_move(yellow.parse_move("dxe3").unwrap()).unwrap();
+        assert_eq!(yellow.en_passant(Player::Red), None);
+        assert_eq!(yellow.get_field('e', 3).unwrap().piece,
+                   Some(Piece { typ: PieceType::Pawn, player: Player::Yellow }));
+
+        // a player out of the game leaves nothing to take en passant
+        board.eliminate(Player::Red, PlayerStatus::Resigned);
+        assert_eq!(board.en_passant(Player::Red), None);
+        assert!(board.parse_move("dxe3").is_err());
+
+        // the opportunity ends once red moves again
+        let mut board = Board::from_notation(&skipped).unwrap();
+        for s in ["Kb8", "Kk11", "Kd1"] {
+            board.apply_move(board.parse_move(s).unwrap()).unwrap();
+        }
+        assert!(board.parse_move("fxe3").is_err());
+    }
+
+    #[test]
+    fn undo_restores_mate_and_turn() {
+        let notation = "1qrrr4kr/8/8/8/8/8/8/kg7/8/8/8/7ky r - - -";
+        let mut board = Board::from_notation(notation).unwrap();
+
+        // green is left without a move but not in check
+        let mov = board.parse_move("Qb6").unwrap();
+        board.apply_move(mov).unwrap();
+        assert_eq!(board.status(Player::Green), PlayerStatus::Stalemated);
+        assert_eq!(board.current_player(), Player::Yellow);
+
+        assert_eq!(board.undo_move(), Some(mov));
+        assert!(! board.is_out(Player::Green));
+        assert_eq!(board.current_player(), Player::Red);
+        assert_eq!(board.to_notation(), notation);
+        assert_eq!(board.undo_move(), None);
+    }
+
+    #[test]
+    fn mate_credit() {
+        // moving the knight uncovers the yellow queen's check
+        let board = Board::from_notation("qyrr5kr/8/8/nr7/8/8/8/kg7/8/8/8/7ky r - - -").unwrap();
+        for (credit, by) in [(MateCredit::Mover, Player::Red), (MateCredit::Checker, Player::Yellow)] {
+            let mut board = board.clone();
+            board.set_rules(Rules { mate_credit: credit, ..Rules::default() });
+            board.apply_move(board.parse_move("Nc5").unwrap()).unwrap();
+            assert_eq!(board.status(Player::Green), PlayerStatus::Checkmated { by: Some(by) });
+        }
+    }
+
+    #[test]
+    fn removed_pieces_come_back_on_undo() {
+        let mut board = Board::new();
+        board.place_pieces();
+        board.set_rules(Rules { eliminated_pieces: EliminatedPieces::Removed, ..Rules::default() });
+        let start = board.to_notation();
+
+        board.apply_move(board.parse_move("e4").unwrap()).unwrap();
+        board.eliminate(Player::Green, PlayerStatus::Resigned);
+        assert!(Coord::all().all(|c| board.get_field(c.0, c.1).unwrap().piece
+                                  .is_none_or(|p| p.player != Player::Green)));
+
+        board.undo_move();
+        assert_eq!(board.to_notation(), start);
+    }
+
+    #[test]
+    fn eliminated_players_lose_their_turn() {
+        let mut board = Board::new();
+        board.place_pieces();
+        board.eliminate(Player::Green, PlayerStatus::TimedOut);
+        assert_eq!(board.current_player(), Player::Red);
+
+        board.eliminate(Player::Red, PlayerStatus::Resigned);
+        assert_eq!(board.current_player(), Player::Yellow);
+        assert_eq!(board.outcome(), Some(GameOutcome::Won(Player::Yellow)));
+        assert_eq!(board.ranking(), [(1, Player::Yellow), (2, Player::Red), (3, Player::Green)]);
+    }
+
+    #[test]
+    fn draw_agreement() {
+        let mut board = Board::new();
+        board.place_pieces();
+        board.eliminate(Player::Red, PlayerStatus::Resigned);
+        assert_eq!(board.outcome(), None);
+
+        board.agree_draw();
+        assert_eq!(board.outcome(), Some(GameOutcome::DrawAgreed));
+        assert_eq!(board.winner(), None);
+        assert_eq!(board.ranking(), [(1, Player::Green), (1, Player::Yellow), (3, Player::Red)]);
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut board = Board::new();
+        board.place_pieces();
+        let shuffle = ["Nf3", "Nc6", "Nf10", "Ng1", "Nb8", "Ng12"];
+        for (i, s) in shuffle.iter().cycle().take(12).enumerate() {
+            assert_eq!(board.outcome(), None, "before move {i}");
+            board.apply_move(board.parse_move(s).unwrap()).unwrap();
+        }
+        assert_eq!(board.repetitions(), 3);
+        assert_eq!(board.outcome(), Some(GameOutcome::Repetition));
+
+        board.undo_move();
+        assert_eq!(board.outcome(), None);
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let mut board = Board::new();
+        board.place_pieces();
+        board.set_halfmoves(FIFTY_MOVES - 1);
+        let mut after_pawn = board.clone();
+
+        board.apply_move(board.parse_move("Nf3").unwrap()).unwrap();
+        assert_eq!(board.outcome(), Some(GameOutcome::FiftyMoves));
+
+        after_pawn.apply_move(after_pawn.parse_move("e4").unwrap()).unwrap();
+        assert_eq!(after_pawn.halfmoves(), 0);
+        assert_eq!(after_pawn.outcome(), None);
+    }
+
+    #[test]
+    fn neutral_pieces_give_no_check() {
+        // green is out, its rook left standing next to the red king
+        let board = Board::from_notation("6rgkr/8/8/8/8/8/8/kg7/8/8/8/7ky r gR - -").unwrap();
+        assert!(! board.is_check(Player::Red));
+        assert!(board.parse_move("Kxg1").is_ok());
+    }
+
+    #[test]
+    fn mated_pieces_change_hands() {
+        let notation = "qyrr5kr/8/8/nr7/8/8/8/kg7/8/8/8/rg6ky r - - -";
+        let mut board = Board::from_notation(notation).unwrap();
+        board.set_rules(Rules { eliminated_pieces: EliminatedPieces::Captured, ..Rules::default() });
+
+        board.apply_move(board.parse_move("Nc5").unwrap()).unwrap();
+        assert_eq!(board.status(Player::Green), PlayerStatus::Checkmated { by: Some(Player::Red) });
+        assert_eq!(board.get_field('e', 12).unwrap().piece,
+                   Some(Piece { typ: PieceType::Rook, player: Player::Red }));
+        assert!(board.get_field('a', 8).unwrap().piece.is_none());
+
+        board.undo_move();
+        assert_eq!(board.to_notation(), notation);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::board::Coord;
+use crate::piece::Player;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CastlingSide {
+    King = 0,
+    Queen = 1,
+}
+
+/// Where king and rook start and end up when castling to one side.
+///
+/// Every player's home rank crosses the seam between two sections, so the
+/// fields are listed explicitly instead of being derived from file letters.
+#[derive(Clone, Copy, Debug)]
+pub struct Castling {
+    pub side: CastlingSide,
+    pub king_from: Coord,
+    pub king_to: Coord,
+    pub rook_from: Coord,
+    pub rook_to: Coord,
+    /// Fields between king and rook that have to be empty.
+    pub empty: &'static [Coord],
+    /// Fields the king passes over or lands on, which must not be attacked.
+    pub king_path: &'static [Coord],
+}
+
+pub const CASTLINGS: [[Castling; 2]; 3] = [
+    [
+        Castling {
+            side: CastlingSide::King,
+            king_from: Coord('e', 1), king_to: Coord('g', 1),
+            rook_from: Coord('h', 1), rook_to: Coord('f', 1),
+            empty: &[Coord('f', 1), Coord('g', 1)],
+            king_path: &[Coord('f', 1), Coord('g', 1)],
+        },
+        Castling {
+            side: CastlingSide::Queen,
+            king_from: Coord('e', 1), king_to: Coord('c', 1),
+            rook_from: Coord('a', 1), rook_to: Coord('d', 1),
+            empty: &[Coord('b', 1), Coord('c', 1), Coord('d', 1)],
+            king_path: &[Coord('d', 1), Coord('c', 1)],
+        },
+    ],
+    [
+        Castling {
+            side: CastlingSide::King,
+            king_from: Coord('d', 8), king_to: Coord('b', 8),
+            rook_from: Coord('a', 8), rook_to: Coord('c', 8),
+            empty: &[Coord('c', 8), Coord('b', 8)],
+            king_path: &[Coord('c', 8), Coord('b', 8)],
+        },
+        Castling {
+            side: CastlingSide::Queen,
+            king_from: Coord('d', 8), king_to: Coord('j', 8),
+            rook_from: Coord('l', 8), rook_to: Coord('i', 8),
+            empty: &[Coord('i', 8), Coord('j', 8), Coord('k', 8)],
+            king_path: &[Coord('i', 8), Coord('j', 8)],
+        },
+    ],
+    [
+        Castling {
+            side: CastlingSide::King,
+            king_from: Coord('i', 12), king_to: Coord('k', 12),
+            rook_from: Coord('l', 12), rook_to: Coord('j', 12),
+            empty: &[Coord('j', 12), Coord('k', 12)],
+            king_path: &[Coord('j', 12), Coord('k', 12)],
+        },
+        Castling {
+            side: CastlingSide::Queen,
+            king_from: Coord('i', 12), king_to: Coord('f', 12),
+            rook_from: Coord('h', 12), rook_to: Coord('e', 12),
+            empty: &[Coord('e', 12), Coord('f', 12), Coord('g', 12)],
+            king_path: &[Coord('e', 12), Coord('f', 12)],
+        },
+    ],
+];
+
+impl Castling {
+    pub fn of(player: Player) -> &'static [Castling; 2] {
+        &CASTLINGS[player as usize]
+    }
+}
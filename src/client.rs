@@ -0,0 +1,89 @@
+//! The client side of the messages in `protocol`, made for polling once per
+//! frame instead of waiting for the server.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use crate::protocol::{ClientMessage, ServerMessage};
+
+pub struct Client {
+    stream: TcpStream,
+    /// Received bytes not yet ending in a line break.
+    buf: Vec<u8>,
+    closed: bool,
+}
+
+impl Client {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self { stream, buf: vec![], closed: false })
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        // a full send buffer must not cut a message in half
+        self.stream.set_nonblocking(false)?;
+        let result = writeln!(self.stream, "{message}");
+        self.stream.set_nonblocking(true)?;
+        result
+    }
+
+    /// The messages that arrived since the last call. Fails once the server
+    /// has closed the connection and everything before was returned.
+    pub fn poll(&mut self) -> io::Result<Vec<ServerMessage>> {
+        let mut chunk = [0; 4096];
+        while ! self.closed {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.buf.extend_from_slice(&chunk[.. n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut messages = vec![];
+        while let Some(end) = self.buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..= end).collect();
+            let message = String::from_utf8_lossy(&line).parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            messages.push(message);
+        }
+
+        if messages.is_empty() && self.closed {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, Player, Server};
+
+    #[test]
+    fn joins_over_loopback() {
+        let mut board = Board::new();
+        board.place_pieces();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || Server::new(board).serve(listener));
+
+        let mut client = Client::connect(addr).unwrap();
+        client.send(&ClientMessage::Join(Player::Green)).unwrap();
+
+        let mut received = vec![];
+        for _ in 0 .. 500 {
+            received.extend(client.poll().unwrap());
+            if received.len() >= 3 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(matches!(received[0], ServerMessage::Rules(_)));
+        assert!(matches!(received[1], ServerMessage::Position(_)));
+        assert_eq!(received[2], ServerMessage::Joined(Player::Green));
+    }
+}
@@ -0,0 +1,230 @@
+//! Chess clocks for the three players.
+//!
+//! Clocks do not read the time themselves; like `Ai::choose_move` they are
+//! handed a `now` that only moves forward, such as `Backend::ticks`. In a
+//! network game the server keeps the clocks and tells its clients the times
+//! left, see `protocol`.
+
+use std::time::Duration;
+
+use crate::piece::Player;
+
+/// How much time the players get, written like `5+3` for five minutes and
+/// three seconds added after each move, or `5d3` for five minutes with the
+/// clock only starting three seconds into each turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    /// Added to a player's time after each of its moves.
+    pub increment: Duration,
+    /// How long into each turn the clock waits before it starts running.
+    pub delay: Duration,
+}
+
+impl std::fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.base.as_secs_f64() / 60.0)?;
+        if ! self.increment.is_zero() {
+            write!(f, "+{}", self.increment.as_secs_f64())?;
+        }
+        if ! self.delay.is_zero() {
+            write!(f, "d{}", self.delay.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time control '{s}'");
+        let parse = |x: &str, unit: f64| x.parse::<f64>().ok()
+            .filter(|x| x.is_finite() && *x >= 0.0)
+            .map(|x| Duration::from_secs_f64(x * unit))
+            .ok_or_else(invalid);
+
+        let (base, increment, delay) = if let Some((base, increment)) = s.split_once('+') {
+            (base, increment, "0")
+        } else if let Some((base, delay)) = s.split_once('d') {
+            (base, "0", delay)
+        } else {
+            (s, "0", "0")
+        };
+
+        let control = TimeControl {
+            base: parse(base, 60.0)?,
+            increment: parse(increment, 1.0)?,
+            delay: parse(delay, 1.0)?,
+        };
+        if control.base.is_zero() {
+            return Err(invalid());
+        }
+        Ok(control)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Clocks {
+    pub control: TimeControl,
+    /// The time left when each player's clock was last stopped.
+    left: [Duration; 3],
+    /// The player whose clock runs, and since when.
+    running: Option<(Player, Duration)>,
+    /// The times left as each turn began, by the number of moves played
+    /// before it, for taking moves back.
+    marks: Vec<[Duration; 3]>,
+}
+
+impl Clocks {
+    pub fn new(control: TimeControl) -> Self {
+        Self { control, left: [control.base; 3], running: None, marks: vec![] }
+    }
+
+    /// The player whose clock runs.
+    pub fn running(&self) -> Option<Player> {
+        self.running.map(|(p, _)| p)
+    }
+
+    /// Starts the clock of `player`, stopping the one running before.
+    pub fn start(&mut self, player: Player, now: Duration) {
+        self.stop(now);
+        self.running = Some((player, now));
+    }
+
+    /// Stops the running clock, crediting the increment unless its time is
+    /// up.
+    pub fn stop(&mut self, now: Duration) {
+        if let Some((player, _)) = self.running {
+            let left = self.left(player, now);
+            self.left[player as usize] = if left.is_zero() { left } else { left + self.control.increment };
+            self.running = None;
+        }
+    }
+
+    /// The time `player` has left at `now`.
+    pub fn left(&self, player: Player, now: Duration) -> Duration {
+        match self.running {
+            Some((p, since)) if p == player => {
+                let spent = now.saturating_sub(since).saturating_sub(self.control.delay);
+                self.left[player as usize].saturating_sub(spent)
+            },
+            _ => self.left[player as usize],
+        }
+    }
+
+    /// The time every player has left at `now`, in the order of
+    /// `Player::ALL`.
+    pub fn times(&self, now: Duration) -> [Duration; 3] {
+        Player::ALL.map(|p| self.left(p, now))
+    }
+
+    /// Sets the time every player has left, with the clock of `running`
+    /// going from `now` on. Clients follow the server's clocks this way.
+    pub fn set(&mut self, left: [Duration; 3], running: Option<Player>, now: Duration) {
+        self.left = left;
+        self.running = running.map(|p| (p, now));
+    }
+
+    /// Remembers the times left as the turn after `moves` moves begins.
+    pub fn mark(&mut self, moves: usize) {
+        self.marks.resize(moves, self.left);
+        self.marks.push(self.left);
+    }
+
+    /// Stops the clocks and sets them back to the times left as the turn
+    /// after `moves` moves began, when taking moves back.
+    pub fn rewind(&mut self, moves: usize) {
+        self.running = None;
+        if let Some(left) = self.marks.get(moves) {
+            self.left = *left;
+        }
+    }
+
+    /// Whether the time of `player` is up.
+    pub fn flagged(&self, player: Player, now: Duration) -> bool {
+        self.left(player, now).is_zero()
+    }
+}
+
+/// Writes a clock's time as `m:ss`, with tenths of a second once less than
+/// ten seconds are left.
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs < 10 {
+        format!("0:0{}.{}", secs, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn time_controls() {
+        let control: TimeControl = "5+3".parse().unwrap();
+        assert_eq!(control, TimeControl { base: secs(300), increment: secs(3), delay: secs(0) });
+        assert_eq!("0.5d2".parse(), Ok(TimeControl { base: secs(30), increment: secs(0), delay: secs(2) }));
+        assert_eq!(control.to_string().parse(), Ok(control));
+        for bad in ["", "0", "5+", "x+3", "-1", "5+3d2"] {
+            assert!(bad.parse::<TimeControl>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn increment() {
+        let mut clocks = Clocks::new("1+2".parse().unwrap());
+        clocks.start(Player::Red, secs(0));
+        assert_eq!(clocks.left(Player::Red, secs(10)), secs(50));
+        assert_eq!(clocks.left(Player::Green, secs(10)), secs(60));
+
+        clocks.start(Player::Green, secs(10));
+        assert_eq!(clocks.left(Player::Red, secs(20)), secs(52));
+        assert_eq!(clocks.running(), Some(Player::Green));
+        assert!(clocks.flagged(Player::Green, secs(70)));
+
+        // no increment once the flag has fallen
+        clocks.stop(secs(80));
+        assert_eq!(clocks.left(Player::Green, secs(90)), secs(0));
+    }
+
+    #[test]
+    fn delay() {
+        let mut clocks = Clocks::new("1d5".parse().unwrap());
+        clocks.start(Player::Yellow, secs(0));
+        assert_eq!(clocks.left(Player::Yellow, secs(4)), secs(60));
+        assert_eq!(clocks.left(Player::Yellow, secs(15)), secs(50));
+        clocks.start(Player::Red, secs(15));
+        assert_eq!(clocks.left(Player::Yellow, secs(100)), secs(50));
+    }
+
+    #[test]
+    fn rewinding() {
+        let mut clocks = Clocks::new("1+2".parse().unwrap());
+        clocks.start(Player::Red, secs(0));
+        clocks.mark(0);
+        clocks.start(Player::Green, secs(10));
+        clocks.mark(1);
+        assert_eq!(clocks.times(secs(30)), [secs(52), secs(40), secs(60)]);
+
+        clocks.rewind(0);
+        assert_eq!(clocks.running(), None);
+        assert_eq!(clocks.times(secs(30)), [secs(60); 3]);
+
+        clocks.set([secs(5), secs(6), secs(7)], Some(Player::Yellow), secs(40));
+        assert_eq!(clocks.times(secs(42)), [secs(5), secs(6), secs(5)]);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(format_time(secs(300)), "5:00");
+        assert_eq!(format_time(secs(61)), "1:01");
+        assert_eq!(format_time(Duration::from_millis(9_350)), "0:09.3");
+    }
+}
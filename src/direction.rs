@@ -0,0 +1,563 @@
+use crate::board::{Board, Field, cadd, csub};
+use crate::piece::Player;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction {
+    ForwardRed,
+    ForwardYellow,
+    ForwardGreen,
+    RedRight,
+    RedLeft,
+    GreenRight,
+    GreenLeft,
+    YellowRight,
+    YellowLeft,
+
+    // diagonals
+    RedYellowToRed,
+    RedYellowToYellow,
+    GreenRedToRed,
+    GreenRedToGreen,
+    GreenYellowToYellow,
+    GreenYellowToGreen,
+    RedToRedYellow,
+    RedToGreenRed,
+    YellowToRedYellow,
+    YellowToGreenYellow,
+    GreenToGreenRed,
+    GreenToGreenYellow,
+}
+
+impl Direction {
+    pub fn is_straight(&self) -> bool {
+        self <= &Direction::YellowLeft
+    }
+
+    pub fn all() -> Vec<Self> {
+        let mut v = vec![];
+        for a in Self::ForwardRed as usize
+                  ..= Self::GreenToGreenYellow as usize {
+            let var = unsafe {std::mem::transmute::<u8, Self>(a as u8)};
+            v.push(var);
+        }
+        v
+    }
+
+    pub fn is_opposite(&self, other: &Self) -> bool {
+        match self {
+            Self::ForwardRed => other == &Self::ForwardGreen || other == &Self::ForwardYellow,
+            Self::ForwardYellow => other == &Self::ForwardGreen || other == &Self::ForwardRed,
+            Self::ForwardGreen => other == &Self::ForwardRed || other == &Self::ForwardYellow,
+            Self::RedRight => other == &Self::RedLeft,
+            Self::RedLeft => other == &Self::RedRight,
+            Self::GreenRight => other == &Self::GreenLeft,
+            Self::GreenLeft => other == &Self::GreenRight,
+            Self::YellowRight => other == &Self::YellowLeft,
+            Self::YellowLeft => other == &Self::YellowRight,
+            _ => false,
+        }
+    }
+
+    pub fn orthogonals(&self) -> Vec<Self> {
+        Self::all().into_iter().filter(|x| x != self && x.is_straight() && ! self.is_opposite(x))
+                          .collect()
+    }
+
+    pub fn next(&self, start: &Field, board: &Board, _player: &Player) -> Option<Field> {
+        let (f, r) = match self {
+            Direction::ForwardRed => {
+                if start.coord.0 >= 'i' {
+                    return None;
+                }
+
+                if start.coord.1 == 8 || start.coord.1 == 12 {
+                    return None;
+                }
+
+                let r = if start.coord.1 == 4
+                    && start.coord.0 >= 'e' {
+                        9
+                    } else {
+                        start.coord.1 + 1
+                    };
+
+                (start.coord.0, r)
+            },
+            Direction::ForwardYellow => {
+                if start.coord.0 <= 'd' {
+                    return None;
+                }
+
+                if start.coord.1 == 8 || start.coord.1 == 1 {
+                    return None;
+                }
+
+                let r = if start.coord.1 == 9 {
+                    if start.coord.0 >= 'i' {
+                        5
+                    } else {
+                        4
+                    }
+                } else if start.coord.1 >= 9 {
+                    start.coord.1 - 1
+                } else if start.coord.1 >= 5 {
+                    start.coord.1 + 1
+                } else {
+                    start.coord.1 - 1
+                };
+
+                (start.coord.0, r)
+            },
+            Direction::ForwardGreen => {
+                if start.coord.0 >= 'e'
+                && start.coord.0 <= 'h' {
+                    return None;
+                }
+
+                if start.coord.1 == 12 || start.coord.1 == 1 {
+                    return None;
+                }
+
+                let r = if start.coord.1 == 5
+                && start.coord.0 >= 'i' {
+                    9
+                } else if start.coord.1 >= 9 {
+                    start.coord.1 + 1
+                } else {
+                    start.coord.1 - 1
+                };
+
+                (start.coord.0, r)
+            },
+            Direction::RedRight => {
+                if start.coord.1 >= 5 {
+                    return None;
+                }
+                let f = cadd(start.coord.0, 1);
+
+                (f, start.coord.1)
+            },
+            Direction::RedLeft => {
+                if start.coord.1 >= 5 {
+                    return None;
+                }
+                let f = csub(start.coord.0, 1);
+
+                (f, start.coord.1)
+            },
+            Direction::GreenRight => {
+                if start.coord.1 <= 4 {
+                    return None;
+                }
+                if start.coord.1 >= 9 {
+                    return None;
+                }
+                let f = if start.coord.0 == 'i' {
+                    'd'
+                } else {
+                    csub(start.coord.0, 1)
+                };
+
+                (f, start.coord.1)
+            },
+            Direction::GreenLeft => {
+                if start.coord.1 <= 4 {
+                    return None;
+                }
+                if start.coord.1 >= 9 {
+                    return None;
+                }
+                let f = if start.coord.0 == 'd' {
+                    'i'
+                } else {
+                    cadd(start.coord.0, 1)
+                };
+
+                (f, start.coord.1)
+            },
+            Direction::YellowRight => {
+                if start.coord.1 <= 8 {
+                    return None;
+                }
+                let f = if start.coord.0 == 'e' {
+                    'i'
+                } else if start.coord.0 >= 'i' {
+                    cadd(start.coord.0, 1)
+                } else {
+                    csub(start.coord.0, 1)
+                };
+
+                (f, start.coord.1)
+            },
+            Direction::YellowLeft => {
+                if start.coord.1 <= 8 {
+                    return None;
+                }
+
+                if start.coord.0 == 'h' {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'i' => 'e',
+                    x if x > 'i'=> csub(x, 1),
+                    a => cadd(a, 1)
+                };
+
+                (f, start.coord.1)
+            },
+            Direction::RedYellowToRed => {
+                if start.coord.0 >= 'i' ||
+                (start.coord.1 >= 5 && start.coord.1 <= 8) {
+                    return None;
+                }
+
+                if start.coord.1 == 1 {
+                    return None;
+                }
+
+                if start.coord.1 >= 9 && (start.coord.1 -
+                    (start.coord.0 as u8 - b'a' + 1) as usize
+                    >= 5) {
+                    return None;
+                }
+
+                if start.coord.1 <= 8 && start.coord.1 >
+                    (start.coord.0 as u8 - b'a' + 1) as usize {
+                    return None;
+                }
+
+                let f = csub(start.coord.0, 1);
+                let r = match start.coord.1 {
+                    9 => 4,
+                    x => x-1,
+                };
+
+                (f, r)
+            },
+            Direction::RedYellowToYellow => {
+                if start.coord.0 <= 'd' ||
+                    (start.coord.1 >= 5 && start.coord.1 <= 8) {
+                    return None;
+                }
+
+                if start.coord.1 == 12 {
+                    return None;
+                }
+
+                if start.coord.1 >= 9 && start.coord.1 <
+                    (start.coord.0 as u8 - b'a' + 1) as usize {
+                    return None;
+                }
+
+                if start.coord.1 <= 4 && (start.coord.1 +
+                    (start.coord.0 as u8 - b'a' + 1) as usize
+                    <= 8) {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'e' => 'i',
+                    x if x <= 'h' => csub(start.coord.0, 1),
+                    _  => cadd(start.coord.0, 1),
+                };
+                let r = match start.coord.1 {
+                    4 => 9,
+                    x => x+1,
+                };
+
+                (f, r)
+            },
+            Direction::GreenRedToRed => {
+                if start.coord.0 >= 'i' {
+                    return None;
+                }
+
+                if start.coord.1 == 1 {
+                    return None;
+                }
+
+                if start.coord.1 +
+                    (start.coord.0 as u8 - b'a' + 1) as usize >= 10{
+                    return None;
+                }
+
+                let f = cadd(start.coord.0, 1);
+                let r = start.coord.1 - 1;
+                (f, r)
+            },
+            Direction::GreenRedToGreen => {
+                if start.coord.1 >= 9 {
+                    return None;
+                }
+
+                if start.coord.1 == 8 {
+                    return None;
+                }
+
+                if start.coord.0 <= 'h' && (start.coord.1 <
+                    (start.coord.0 as u8 - b'a' + 1) as usize) {
+                    return None;
+                }
+
+                if start.coord.0 >= 'i'
+                    && ((start.coord.0 as u8 - b'a' + 1) as usize
+                        - start.coord.1) >= 5 {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'd' => 'i',
+                    x => cadd(x, 1),
+                };
+                let r = start.coord.1 + 1;
+                (f, r)
+            },
+            Direction::GreenYellowToYellow => {
+                if start.coord.1 <= 4 || start.coord.0 <= 'd' {
+                    return None;
+                }
+
+                if start.coord.1 == 12 {
+                    return None;
+                }
+
+                if start.coord.0 <= 'h' && (start.coord.1 -
+                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 3 {
+                    return None;
+                }
+
+                if start.coord.1 <= 8
+                    && ((start.coord.0 as u8 - b'a' + 1) as usize - start.coord.1
+                    ) <= 3 {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'i' => 'e',
+                    x if x >= 'i' => csub(x, 1),
+                    x  => cadd(x, 1),
+                };
+                let r = match start.coord.1 {
+                    5 => 9,
+                    x if x <= 8 => x - 1,
+                    x  => x + 1,
+                };
+
+                (f, r)
+            },
+            Direction::GreenYellowToGreen => {
+                if start.coord.1 <= 4 || (start.coord.0 >= 'e'
+                                          && start.coord.0 <= 'h') {
+                    return None;
+                }
+
+                if start.coord.1 == 8 {
+                    return None;
+                }
+
+                if start.coord.0 <= 'd' && (start.coord.1 +
+                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 8 {
+                    return None;
+                }
+
+                if start.coord.0 >= 'i'
+                    && ((start.coord.0 as u8 - b'a' + 1) as usize) < start.coord.1 {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'i' => 'd',
+                    x  => csub(x, 1),
+                };
+                let r = match start.coord.1 {
+                    9 => 5,
+                    x if x > 9 => x - 1,
+                    x => x + 1,
+                };
+
+                (f, r)
+            },
+            Direction::RedToRedYellow => {
+                if start.coord.0 >= 'i' || (start.coord.1 >= 5
+                                          && start.coord.1 <= 8) {
+                    return None;
+                }
+
+                if start.coord.0 == 'h' {
+                    return None;
+                }
+
+                if start.coord.0 <= 'd' && (start.coord.1 >
+                    (start.coord.0 as u8 - b'a' + 1) as usize) {
+                    return None;
+                }
+
+                if start.coord.1 >= 9
+                    && (start.coord.1 - (start.coord.0 as u8 - b'a' + 1) as usize) >= 5 {
+                    return None;
+                }
+
+                let f = cadd(start.coord.0, 1);
+                let r = match start.coord.1 {
+                    4 => 9,
+                    x => x + 1,
+                };
+
+                (f, r)
+            },
+            Direction::RedToGreenRed => {
+                if start.coord.1 >= 9 || start.coord.0 >= 'i' {
+                    return None;
+                }
+
+                if start.coord.0 == 'a' {
+                    return None;
+                }
+
+                if start.coord.1 >= 5 && (start.coord.1 +
+                    (start.coord.0 as u8 - b'a' + 1) as usize) >= 10 {
+                    return None;
+                }
+
+                if start.coord.0 >= 'e'
+                    && (start.coord.1 + (start.coord.0 as u8 - b'a' + 1) as usize) >= 10 {
+                    return None;
+                }
+
+                let f = csub(start.coord.0, 1);
+                let r = start.coord.1 + 1;
+
+                (f, r)
+            },
+            Direction::YellowToRedYellow => {
+                if (start.coord.1 <= 8
+                    && start.coord.1 >= 5) || start.coord.0 <= 'd' {
+                    return None;
+                }
+
+                if start.coord.0 == 'h' {
+                    return None;
+                }
+
+                if start.coord.1 <= 4 && (start.coord.1 +
+                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 8 {
+                    return None;
+                }
+
+                if start.coord.0 >= 'i'
+                    && start.coord.1 < (start.coord.0 as u8 - b'a' + 1) as usize {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'i' => 'e',
+                    x if x >= 'i' => csub(x, 1),
+                    x => cadd(x, 1),
+                };
+                let r = match start.coord.1 {
+                    9 => 4,
+                    x => x - 1,
+                };
+
+                (f, r)
+            },
+            Direction::YellowToGreenYellow => {
+                if start.coord.0 <= 'd'
+                    || start.coord.1 <= 4 {
+                    return None;
+                }
+
+                if start.coord.0 == 'l' {
+                    return None;
+                }
+
+                if start.coord.0 <= 'h' && (start.coord.1 -
+                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 3 {
+                    return None;
+                }
+
+                if start.coord.1 <= 8
+                    && ((start.coord.0 as u8 - b'a' + 1) as usize - start.coord.1) <= 3 {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'e' => 'i',
+                    x if x >= 'i' => cadd(x, 1),
+                    x => csub(x, 1),
+                };
+                let r = match start.coord.1 {
+                    9 => 5,
+                    x if x >= 9 => x - 1,
+                    x => x + 1,
+                };
+
+                (f, r)
+            },
+            Direction::GreenToGreenRed => {
+                if start.coord.1 >= 9 || (
+                    start.coord.0 <= 'h' &&  start.coord.0 >= 'e') {
+                    return None;
+                }
+
+                if start.coord.0 == 'a' {
+                    return None;
+                }
+
+                if start.coord.1 <= 4 && start.coord.1 <
+                    (start.coord.0 as u8 - b'a' + 1) as usize {
+                    return None;
+                }
+
+                if start.coord.0 >= 'i'
+                    && ((start.coord.0 as u8 - b'a' + 1) as usize
+                    - start.coord.1) >= 5 {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'i' => 'd',
+                    x => csub(x, 1),
+                };
+                let r = start.coord.1 - 1;
+                (f, r)
+            },
+            Direction::GreenToGreenYellow => {
+                if (start.coord.0 <= 'h' && start.coord.0 >= 'e')
+                    || start.coord.1 <= 4 {
+                    return None;
+                }
+
+                if start.coord.0 == 'l' {
+                    return None;
+                }
+
+                if start.coord.0 <= 'd' && (start.coord.1 +
+                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 8 {
+                    return None;
+                }
+
+                if start.coord.1 >= 9
+                    && ((start.coord.0 as u8 - b'a' + 1) as usize)
+                        < start.coord.1 {
+                    return None;
+                }
+
+                let f = match start.coord.0 {
+                    'd' => 'i',
+                    x => cadd(x, 1),
+                };
+                let r = match start.coord.1 {
+                    5 => 9,
+                    x if x >= 9 => x + 1,
+                    x => x - 1,
+                };
+
+                (f, r)
+            },
+        };
+
+        board.get_field(f, r).cloned()
+    }
+}
@@ -0,0 +1,244 @@
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+
+use crate::backend::{self, Color, Event, ImageHandle};
+
+const FONT_SIZE: f32 = 18.0;
+
+/// A decoded PNG image, four bytes per pixel.
+struct Rgba {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+/// Draws into an RGBA buffer in memory instead of a window, for writing
+/// pictures of positions to files.
+pub struct ImageBackend {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    color: Color,
+    font: FontVec,
+    images: Vec<Rgba>,
+}
+
+fn decode_png(data: &[u8]) -> Result<Rgba, png::DecodingError> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let buf = &buf[.. info.buffer_size()];
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buf.to_vec(),
+        png::ColorType::Rgb => buf.chunks(3).flat_map(|p| [p[0], p[1], p[2], 0xff]).collect(),
+        png::ColorType::GrayscaleAlpha => buf.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|g| [*g, *g, *g, 0xff]).collect(),
+        png::ColorType::Indexed => unreachable!("palettes are expanded by the decoder"),
+    };
+    Ok(Rgba { width: info.width as usize, height: info.height as usize, pixels })
+}
+
+impl ImageBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        let font = std::fs::read("./FiraCode.ttf").unwrap();
+        Self {
+            width,
+            height,
+            pixels: vec![0xff; (width * height * 4) as usize],
+            color: Color::WHITE,
+            font: FontVec::try_from_vec(font).unwrap(),
+            images: vec![],
+        }
+    }
+
+    /// Mixes `color` into the pixel at `x`, `y` with `alpha` from 0 to 1.
+    fn blend(&mut self, x: i32, y: i32, color: Color, alpha: f32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        for (c, new) in [color.0, color.1, color.2].into_iter().enumerate() {
+            let old = self.pixels[i + c] as f32;
+            self.pixels[i + c] = (old + (new as f32 - old) * alpha.clamp(0.0, 1.0)).round() as u8;
+        }
+    }
+
+    fn draw_point(&mut self, x: i32, y: i32) {
+        self.blend(x, y, self.color, 1.0);
+    }
+
+    fn scale(&self) -> PxScale {
+        self.font.pt_to_px_scale(FONT_SIZE).unwrap_or(PxScale::from(FONT_SIZE))
+    }
+
+    /// The picture drawn so far, encoded as PNG.
+    pub fn encode_png(&self) -> Vec<u8> {
+        let mut data = vec![];
+        let mut encoder = png::Encoder::new(&mut data, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&self.pixels).unwrap();
+        writer.finish().unwrap();
+        data
+    }
+}
+
+impl backend::Backend for ImageBackend {
+    fn draw_line(&mut self, a: (i32, i32), b: (i32, i32)) {
+        let (dx, dy) = ((b.0 - a.0).abs(), -(b.1 - a.1).abs());
+        let (sx, sy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+        let (mut x, mut y) = a;
+        let mut err = dx + dy;
+        loop {
+            self.draw_point(x, y);
+            if (x, y) == b {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn clear(&mut self) {
+        let Color(r, g, b) = self.color;
+        for p in self.pixels.chunks_mut(4) {
+            p.copy_from_slice(&[r, g, b, 0xff]);
+        }
+    }
+
+    fn win_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn poll_event(&mut self) -> Vec<Event> {
+        vec![]
+    }
+
+    fn text_size(&self, string: &str) -> (u32, u32) {
+        let font = self.font.as_scaled(self.scale());
+        let mut width = 0.0;
+        let mut last = None;
+        for c in string.chars() {
+            let id = font.glyph_id(c);
+            if let Some(l) = last {
+                width += font.kern(l, id);
+            }
+            width += font.h_advance(id);
+            last = Some(id);
+        }
+        (width.ceil() as u32, font.height().ceil() as u32)
+    }
+
+    fn render_text(&mut self, text: &str, x: i32, y: i32, color: Color) {
+        let scale = self.scale();
+        let font = self.font.as_scaled(scale);
+        let mut caret = x as f32;
+        let baseline = y as f32 + font.ascent();
+        let mut last = None;
+        let mut coverage = vec![];
+
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(l) = last {
+                caret += font.kern(l, id);
+            }
+            let glyph = id.with_scale_and_position(scale, ab_glyph::point(caret, baseline));
+            caret += font.h_advance(id);
+            last = Some(id);
+
+            if let Some(outline) = self.font.outline_glyph(glyph) {
+                let bounds = outline.px_bounds();
+                outline.draw(|gx, gy, c| coverage.push((bounds.min.x as i32 + gx as i32,
+                                                         bounds.min.y as i32 + gy as i32, c)));
+            }
+        }
+
+        for (px, py, c) in coverage {
+            self.blend(px, py, color, c);
+        }
+    }
+
+    fn load_image(&mut self, data: &[u8]) -> ImageHandle {
+        let image = decode_png(data).unwrap_or_else(|e| {
+            eprintln!("could not decode image: {e}");
+            Rgba { width: 0, height: 0, pixels: vec![] }
+        });
+        self.images.push(image);
+        ImageHandle(self.images.len() - 1)
+    }
+
+    fn draw_image(&mut self, image: ImageHandle, x: i32, y: i32, w: i32, h: i32) {
+        let image = &self.images[image.0];
+
+        // every target pixel averages the source pixels it covers
+        let mut out = vec![];
+        for ty in 0 .. h.max(0) as usize {
+            let y0 = ty * image.height / h as usize;
+            let y1 = ((ty + 1) * image.height / h as usize).max(y0 + 1);
+            for tx in 0 .. w.max(0) as usize {
+                let x0 = tx * image.width / w as usize;
+                let x1 = ((tx + 1) * image.width / w as usize).max(x0 + 1);
+
+                let mut sum = [0.0; 4];
+                for sy in y0 .. y1.min(image.height) {
+                    for sx in x0 .. x1.min(image.width) {
+                        let p = &image.pixels[(sy * image.width + sx) * 4 ..][.. 4];
+                        let a = p[3] as f32 / 255.0;
+                        sum[0] += p[0] as f32 * a;
+                        sum[1] += p[1] as f32 * a;
+                        sum[2] += p[2] as f32 * a;
+                        sum[3] += a;
+                    }
+                }
+                if sum[3] > 0.0 {
+                    let n = ((y1 - y0) * (x1 - x0)) as f32;
+                    let color = Color((sum[0] / sum[3]) as u8, (sum[1] / sum[3]) as u8,
+                                      (sum[2] / sum[3]) as u8);
+                    out.push((x + tx as i32, y + ty as i32, color, sum[3] / n));
+                }
+            }
+        }
+
+        for (px, py, color, alpha) in out {
+            self.blend(px, py, color, alpha);
+        }
+    }
+
+    fn present(&mut self) {}
+
+    fn ticks(&self) -> u64 {
+        0
+    }
+
+    fn log(string: String) {
+        println!("{}", string);
+    }
+
+    fn elog(string: String) {
+        eprintln!("{}", string);
+    }
+
+    fn read_file(&self, path: &str) -> Vec<u8> {
+        std::fs::read(path).unwrap()
+    }
+
+    fn write_file(&self, path: &str, data: &[u8]) {
+        if let Err(e) = std::fs::write(path, data) {
+            eprintln!("could not write {path}: {e}");
+        }
+    }
+}
@@ -0,0 +1,491 @@
+//! An index-based copy of a `Board` for fast move generation.
+//!
+//! Fields are numbered from 0 to 95 in the order of `Coord::all`. Where one
+//! step in every `Direction` leads from each field is worked out once with
+//! `Direction::next` and kept in tables, so walking the board is a lookup
+//! instead of character arithmetic, and trying out a move copies a flat
+//! array of pieces instead of cloning the sections.
+//!
+//! The move generation follows `Field::get_possible_moves` exactly, down to
+//! the order in which targets are found.
+
+use std::sync::OnceLock;
+
+use crate::board::{pawn_capture_dirs, pawn_home_rank, Board, Coord, DIAGONAL_DIRS, STRAIGHT_DIRS};
+use crate::castling::{Castling, CastlingSide};
+use crate::direction::Direction;
+use crate::piece::{Piece, PieceType, Player};
+
+pub const FIELD_COUNT: usize = 96;
+const DIRECTION_COUNT: usize = 21;
+
+struct Tables {
+    /// `neighbours[dir][field]` is the field one step from `field` in `dir`.
+    neighbours: [[Option<u8>; FIELD_COUNT]; DIRECTION_COUNT],
+    /// `pawn_dirs[player][field]` is the way a pawn of `player` moves on.
+    pawn_dirs: [[Direction; FIELD_COUNT]; 3],
+    knight: Vec<Vec<usize>>,
+    king: Vec<Vec<usize>>,
+}
+
+impl Tables {
+    fn new() -> Self {
+        let board = Board::new();
+        let mut neighbours = [[None; FIELD_COUNT]; DIRECTION_COUNT];
+        let mut pawn_dirs = [[Direction::ForwardRed; FIELD_COUNT]; 3];
+
+        for coord in Coord::all() {
+            let field = board.get_field(coord.0, coord.1).unwrap();
+            for dir in Direction::all() {
+                neighbours[dir as usize][coord.index()] = dir.next(field, &board, &Player::Red)
+                    .map(|f| f.coord.index() as u8);
+            }
+            for player in Player::ALL {
+                pawn_dirs[player as usize][coord.index()] = field.get_pawn_dirs(player)[0];
+            }
+        }
+
+        let step = |dir: Direction, field: usize| neighbours[dir as usize][field].map(usize::from);
+
+        let knight = (0 .. FIELD_COUNT).map(|field| {
+            let mut targets = vec![];
+            let mut add = |target: Option<usize>| if let Some(t) = target {
+                if ! targets.contains(&t) {
+                    targets.push(t);
+                }
+            };
+
+            // 1 - 2
+            for dir in STRAIGHT_DIRS {
+                for orth in dir.orthogonals() {
+                    add(step(dir, field).and_then(|f| step(orth, f)).and_then(|f| step(orth, f)));
+                }
+            }
+            // 2 - 1
+            for dir in STRAIGHT_DIRS {
+                for orth in dir.orthogonals() {
+                    add(step(dir, field).and_then(|f| step(dir, f)).and_then(|f| step(orth, f)));
+                }
+            }
+            targets
+        }).collect();
+
+        let king = (0 .. FIELD_COUNT).map(|field| {
+            STRAIGHT_DIRS.iter().chain(&DIAGONAL_DIRS)
+                .filter_map(|dir| step(*dir, field))
+                .collect()
+        }).collect();
+
+        Tables { neighbours, pawn_dirs, knight, king }
+    }
+
+    fn step(&self, dir: Direction, field: usize) -> Option<usize> {
+        self.neighbours[dir as usize][field].map(usize::from)
+    }
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(Tables::new)
+}
+
+/// The pieces and the state relevant for move generation of a `Board`.
+#[derive(Clone, Copy, Debug)]
+pub struct IndexedBoard {
+    pieces: [Option<Piece>; FIELD_COUNT],
+    /// The skipped field and the pawn of each player's last double step.
+    en_passant: [Option<(usize, usize)>; 3],
+    castling: [[bool; 2]; 3],
+    /// The players who are out, whose pieces left on the board are neutral.
+    out: [bool; 3],
+}
+
+impl IndexedBoard {
+    pub fn from_board(board: &Board) -> Self {
+        let mut pieces = [None; FIELD_COUNT];
+        for s in board.sections() {
+            for f in s.fields.iter().flatten() {
+                pieces[f.coord.index()] = f.piece;
+            }
+        }
+
+        Self {
+            pieces,
+            en_passant: Player::ALL.map(|p| board.en_passant(p)
+                                        .filter(|_| board.rules().variant.en_passant())
+                                        .map(|e| (e.target.index(), e.pawn.index()))),
+            castling: Player::ALL.map(|p| [CastlingSide::King, CastlingSide::Queen]
+                                      .map(|s| board.has_castling_right(p, s))),
+            out: Player::ALL.map(|p| board.is_out(p)),
+        }
+    }
+
+    pub fn piece(&self, field: usize) -> Option<Piece> {
+        self.pieces[field]
+    }
+
+    /// See `Board::en_passant_capture`.
+    fn en_passant_capture(&self, player: Player, to: usize) -> Option<usize> {
+        Player::ALL.into_iter()
+            .filter(|p| *p != player)
+            .filter_map(|p| self.en_passant[p as usize].map(|e| (p, e)))
+            .find(|(p, (target, pawn))| *target == to && self.pieces[*pawn]
+                  .is_some_and(|x| x.player == *p && x.typ == PieceType::Pawn))
+            .map(|(_, (_, pawn))| pawn)
+    }
+
+    /// Appends the fields the piece on `field` could move to if check did
+    /// not matter, like `Field::get_possible_moves_unchecked`.
+    pub fn pseudo_targets(&self, field: usize, targets: &mut Vec<usize>) {
+        let t = tables();
+        let piece = self.pieces[field].unwrap();
+        let player = piece.player;
+        let start = targets.len();
+        let not_own = |to: usize| self.pieces[to].is_none_or(|p| p.player != player);
+
+        match piece.typ {
+            PieceType::Pawn => {
+                for dir in pawn_capture_dirs(player) {
+                    if let Some(to) = t.step(dir, field) {
+                        match self.pieces[to] {
+                            Some(p) => if p.player != player {
+                                targets.push(to);
+                            },
+                            None => if self.en_passant_capture(player, to).is_some()
+                                && ! targets[start ..].contains(&to) {
+                                    targets.push(to);
+                                },
+                        }
+                    }
+                }
+
+                let dir = t.pawn_dirs[player as usize][field];
+                if let Some(to) = t.step(dir, field).filter(|to| self.pieces[*to].is_none()) {
+                    targets.push(to);
+                    if Coord::from_index(field).1 == pawn_home_rank(player) {
+                        if let Some(to) = t.step(dir, to).filter(|to| self.pieces[*to].is_none()) {
+                            targets.push(to);
+                        }
+                    }
+                }
+            },
+            PieceType::Rook | PieceType::Bishop | PieceType::Queen => {
+                let (first, second): (&[Direction], &[Direction]) = match piece.typ {
+                    PieceType::Rook => (&STRAIGHT_DIRS, &[]),
+                    PieceType::Bishop => (&DIAGONAL_DIRS, &[]),
+                    _ => (&STRAIGHT_DIRS, &DIAGONAL_DIRS),
+                };
+
+                for dir in first.iter().chain(second) {
+                    let mut current = field;
+                    while let Some(to) = t.step(*dir, current) {
+                        if let Some(p) = self.pieces[to] {
+                            if p.player != player {
+                                targets.push(to);
+                            }
+                            break;
+                        }
+                        targets.push(to);
+                        current = to;
+                    }
+                }
+            },
+            PieceType::Knight => targets.extend(t.knight[field].iter().filter(|to| not_own(**to))),
+            PieceType::King => targets.extend(t.king[field].iter().filter(|to| not_own(**to))),
+        }
+
+        let mut i = start;
+        while i < targets.len() {
+            if targets[start .. i].contains(&targets[i]) {
+                targets.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn king(&self, player: Player) -> Option<usize> {
+        self.pieces.iter().position(|p| *p == Some(Piece { typ: PieceType::King, player }))
+    }
+
+    pub fn is_check(&self, player: Player) -> bool {
+        self.attacks_king(player, |p| p != player)
+    }
+
+    /// Whether a piece of `by` attacks the king of `player`.
+    pub fn is_check_by(&self, player: Player, by: Player) -> bool {
+        by != player && self.attacks_king(player, |p| p == by)
+    }
+
+    /// Whether a piece of a player still in and picked by `attacker` attacks
+    /// the king of `player`.
+    fn attacks_king(&self, player: Player, attacker: impl Fn(Player) -> bool) -> bool {
+        let king = match self.king(player) {
+            Some(k) => k,
+            None => return false,
+        };
+
+        let mut targets = vec![];
+        for field in 0 .. FIELD_COUNT {
+            if self.pieces[field].is_some_and(|p| attacker(p.player) && ! self.out[p.player as usize]) {
+                targets.clear();
+                self.pseudo_targets(field, &mut targets);
+                if targets.contains(&king) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether moving the piece on `from` to `to` leaves its own king safe.
+    /// Castling is not handled here but in `can_castle`.
+    fn keeps_king_safe(&self, from: usize, to: usize) -> bool {
+        let piece = self.pieces[from].unwrap();
+        let mut board = *self;
+        board.pieces[to] = Some(piece);
+        board.pieces[from] = None;
+        if piece.typ == PieceType::Pawn {
+            if let Some(c) = self.en_passant_capture(piece.player, to) {
+                board.pieces[c] = None;
+            }
+        }
+        ! board.is_check(piece.player)
+    }
+
+    /// Whether moving to `to` takes the king of another player still in.
+    fn captures_king(&self, player: Player, to: usize) -> bool {
+        self.pieces[to].is_some_and(|p| p.player != player && p.typ == PieceType::King
+                                    && ! self.out[p.player as usize])
+    }
+
+    /// Whether any piece of `player` can legally take a king, which then
+    /// is the only kind of move allowed.
+    pub fn can_capture_king(&self, player: Player) -> bool {
+        let mut targets = vec![];
+        (0 .. FIELD_COUNT).any(|field| {
+            if ! self.pieces[field].is_some_and(|p| p.player == player) {
+                return false;
+            }
+            targets.clear();
+            self.pseudo_targets(field, &mut targets);
+            targets.iter().any(|to| self.captures_king(player, *to)
+                               && self.keeps_king_safe(field, *to))
+        })
+    }
+
+    /// See `Board::can_castle`.
+    pub fn can_castle(&self, player: Player, castling: &Castling) -> bool {
+        if ! self.castling[player as usize][castling.side as usize] {
+            return false;
+        }
+
+        let king_from = castling.king_from.index();
+        let is_own = |c: Coord, typ: PieceType| self.pieces[c.index()] == Some(Piece { typ, player });
+        if ! is_own(castling.king_from, PieceType::King)
+            || ! is_own(castling.rook_from, PieceType::Rook) {
+                return false;
+            }
+
+        if castling.empty.iter().any(|c| self.pieces[c.index()].is_some()) {
+            return false;
+        }
+
+        if self.is_check(player) {
+            return false;
+        }
+
+        castling.king_path.iter().all(|c| {
+            let mut board = *self;
+            board.pieces[c.index()] = board.pieces[king_from];
+            board.pieces[king_from] = None;
+            ! board.is_check(player)
+        })
+    }
+
+    /// The legal targets of the piece on `field`, like
+    /// `Field::get_possible_moves`. `king_capture` tells whether its owner
+    /// can take a king with any piece, see `can_capture_king`.
+    fn legal_targets_with(&self, field: usize, king_capture: bool) -> Vec<usize> {
+        let piece = self.pieces[field].unwrap();
+        let mut targets = vec![];
+        self.pseudo_targets(field, &mut targets);
+
+        if king_capture {
+            targets.retain(|to| self.captures_king(piece.player, *to));
+        }
+        targets.retain(|to| self.keeps_king_safe(field, *to));
+
+        if ! king_capture && piece.typ == PieceType::King {
+            targets.extend(Castling::of(piece.player).iter()
+                           .filter(|c| c.king_from.index() == field
+                                   && self.can_castle(piece.player, c))
+                           .map(|c| c.king_to.index()));
+        }
+        targets
+    }
+
+    /// The legal targets of the piece on `field`.
+    pub fn legal_targets(&self, field: usize) -> Vec<usize> {
+        match self.pieces[field] {
+            Some(p) => self.legal_targets_with(field, self.can_capture_king(p.player)),
+            None => vec![],
+        }
+    }
+
+    /// The legal targets of every piece of `player`, by field.
+    pub fn all_legal_targets(&self, player: Player) -> Vec<(usize, Vec<usize>)> {
+        let king_capture = self.can_capture_king(player);
+        (0 .. FIELD_COUNT)
+            .filter(|f| self.pieces[*f].is_some_and(|p| p.player == player))
+            .map(|f| (f, self.legal_targets_with(f, king_capture)))
+            .collect()
+    }
+
+    /// Whether `player` has no legal move at all.
+    pub fn is_mate(&self, player: Player) -> bool {
+        if self.can_capture_king(player) {
+            return false;
+        }
+        ! (0 .. FIELD_COUNT).any(|f| self.pieces[f].is_some_and(|p| p.player == player)
+                                 && ! self.legal_targets_with(f, false).is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Positions from random games, plus one where every player can castle.
+    fn positions() -> Vec<Board> {
+        let mut start = Board::new();
+        start.place_pieces();
+
+        let mut castling = start.clone();
+        for c in Castling::of(Player::Red).iter()
+            .chain(Castling::of(Player::Green))
+            .chain(Castling::of(Player::Yellow)) {
+                for e in c.empty {
+                    castling.get_field_mut(e.0, e.1).unwrap().piece = None;
+                }
+            }
+
+        let mut positions = vec![start.clone(), castling];
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        for _ in 0 .. 6 {
+            let mut board = start.clone();
+            for _ in 0 .. 40 {
+                if board.winner().is_some() {
+                    break;
+                }
+                let moves = board.legal_moves(board.current_player());
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                board.apply_move_unchecked(moves[seed as usize % moves.len()]);
+                positions.push(board.clone());
+            }
+        }
+        positions
+    }
+
+    #[test]
+    fn neighbours_match_directions() {
+        let board = Board::new();
+        for coord in Coord::all() {
+            let field = board.get_field(coord.0, coord.1).unwrap();
+            for dir in Direction::all() {
+                let expected = dir.next(field, &board, &Player::Red).map(|f| f.coord);
+                assert_eq!(tables().step(dir, coord.index()).map(Coord::from_index), expected,
+                           "{dir:?} from {coord}");
+            }
+        }
+    }
+
+    #[test]
+    fn same_moves_as_fields() {
+        for board in positions() {
+            let indexed = IndexedBoard::from_board(&board);
+            for f in board.get_fields() {
+                if f.piece.is_none() {
+                    continue;
+                }
+
+                let mut targets = vec![];
+                indexed.pseudo_targets(f.coord.index(), &mut targets);
+                let targets: Vec<_> = targets.into_iter().map(Coord::from_index).collect();
+                assert_eq!(targets, f.get_possible_moves_unchecked(&board), "{}", board.to_notation());
+            }
+        }
+    }
+
+    /// The legal targets of the pieces of the player to move but for
+    /// castling, found the way `Field` did before this board existed: by
+    /// playing each target on a copy and looking for a piece that reaches
+    /// the king. It knows nothing of players being out.
+    fn legacy_targets(board: &Board) -> Vec<(Coord, Vec<Coord>)> {
+        let player = board.current_player();
+        let in_check = |b: &Board| b.get_king_field(player)
+            .is_some_and(|k| b.get_possible_move_targets_unchecked(player).contains(&k.coord));
+
+        let mut targets = vec![];
+        for f in board.get_fields() {
+            let Some(piece) = f.piece.filter(|p| p.player == player) else { continue };
+            let safe: Vec<_> = f.get_possible_moves_unchecked(board).into_iter().filter(|to| {
+                let mut b = board.clone();
+                b.get_field_mut(to.0, to.1).unwrap().piece = f.piece;
+                b.get_field_mut(f.coord.0, f.coord.1).unwrap().piece = None;
+                if piece.typ == PieceType::Pawn {
+                    if let Some(c) = board.en_passant_capture(player, *to) {
+                        b.get_field_mut(c.0, c.1).unwrap().piece = None;
+                    }
+                }
+                ! in_check(&b)
+            }).collect();
+            let king_captures: Vec<_> = safe.iter().copied()
+                .filter(|to| board.get_field(to.0, to.1).unwrap().piece
+                        .is_some_and(|p| p.player != player && p.typ == PieceType::King))
+                .collect();
+            targets.push((f.coord, safe, king_captures));
+        }
+
+        // a player who can take a king has to
+        let king_capture = targets.iter().any(|t| ! t.2.is_empty());
+        targets.into_iter()
+            .map(|(coord, safe, king_captures)| (coord, if king_capture { king_captures } else { safe }))
+            .collect()
+    }
+
+    #[test]
+    fn same_legal_moves_as_before() {
+        // the old way is slow, every tenth position has to do
+        for board in positions().into_iter().step_by(10) {
+            if Player::ALL.iter().any(|p| board.is_out(*p)) {
+                continue;
+            }
+            let indexed = IndexedBoard::from_board(&board);
+            for (coord, expected) in legacy_targets(&board) {
+                // castling has its own tests in `board`
+                let piece = board.get_field(coord.0, coord.1).unwrap().piece.unwrap();
+                let castling = |to: &Coord| piece.typ == PieceType::King && Castling::of(piece.player)
+                    .iter().any(|c| c.king_from == coord && c.king_to == *to);
+                let targets: Vec<_> = indexed.legal_targets(coord.index()).into_iter()
+                    .map(Coord::from_index).filter(|to| ! castling(to)).collect();
+                assert_eq!(targets, expected, "{coord} in {}", board.to_notation());
+            }
+        }
+    }
+
+    #[test]
+    fn same_checks_as_fields() {
+        for board in positions() {
+            let indexed = IndexedBoard::from_board(&board);
+            for player in Player::ALL {
+                let expected = board.get_king_field(player).is_some_and(|k| {
+                    board.get_possible_move_targets_unchecked(player).contains(&k.coord)
+                });
+                assert_eq!(indexed.is_check(player), expected, "{}", board.to_notation());
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+pub mod ai;
+pub mod board;
+pub mod castling;
+pub mod client;
+pub mod clock;
+pub mod direction;
+pub mod indexed;
+pub mod moves;
+pub mod notation;
+pub mod perft;
+pub mod piece;
+pub mod protocol;
+pub mod record;
+pub mod rules;
+pub mod server;
+pub mod variant;
+pub mod websocket;
+pub mod zobrist;
+
+pub use ai::{Ai, Strategy};
+pub use board::{Board, Coord, EnPassant, Field, FieldType, Section};
+pub use castling::{Castling, CastlingSide};
+pub use client::Client;
+pub use clock::{Clocks, TimeControl};
+pub use direction::Direction;
+pub use indexed::IndexedBoard;
+pub use moves::{IllegalMove, Move, MoveKind};
+pub use notation::NotationError;
+pub use protocol::{ClientMessage, ProtocolError, ServerMessage};
+pub use record::GameRecord;
+pub use rules::{EliminatedPieces, GameOutcome, MateCredit, PlayerStatus, Rules};
+pub use piece::{Piece, PieceType, Player, COLOR_LETTERS, PIECE_LETTERS};
+pub use server::Server;
+pub use variant::Variant;
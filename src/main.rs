@@ -1,1233 +1,57 @@
 mod backend;
 
-#[cfg(not(target_family = "wasm"))]
-mod sdl_backend;
-
-#[cfg(target_family = "wasm")]
-mod wasm_backend;
-
-use crate::backend::Backend;
-
-#[cfg(not(target_family = "wasm"))]
-type BackendType<'a, 'b> = sdl_backend::SdlBackend<'a, 'b>;
-
-#[cfg(target_family = "wasm")]
-type BackendType = wasm_backend::WasmBackend;
-
-#[cfg(target_family = "wasm")]
-macro_rules! println {
-     ($($arg:tt)*) => {
-         BackendType::log(::std::format!($($arg)*));
-     };
- }
-
-#[cfg(target_family = "wasm")]
-macro_rules! eprintln {
-     ($($arg:tt)*) => {
-         BackendType::elog(::std::format!($($arg)*));
-     };
- }
-
-use std::collections::HashMap;
-use backend::{Color, Event, MouseButton};
-
-#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
-pub struct Coord(char, usize);
-
-#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
-pub enum FieldType {
-    #[default] WHITE,
-    BLACK,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum Player {
-    Red = 0,
-    Green = 1,
-    Yellow = 2,
-}
-
-impl std::fmt::Display for Player {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Player::Red => write!(f, "red"),
-            Player::Green => write!(f, "green"),
-            Player::Yellow => write!(f, "yellow"),
-        }
-    }
-}
-
-
-impl Player {
-    pub fn next(&self) -> Self {
-        match self {
-            Player::Red => Player::Green,
-            Player::Green => Player::Yellow,
-            Player::Yellow => Player::Red,
-        }
-    }
-
-    pub fn is_mate(&self, board: &Board) -> bool {
-        let fields = board.get_fields();
-        for f in fields {
-            if let Some(p) = f.piece {
-                if p.player == *self &&
-                    (! f.get_possible_moves(board).is_empty()) {
-                        return false;
-                }
-            }
-        }
-
-        true
-    }
-
-    pub fn can_capture_king(&self, board: &Board) -> bool {
-        let fields = board.get_fields();
-        for f in fields {
-            if let Some(p) = f.piece {
-                if p.player == *self {
-                    let (_, moves) = f.get_possible_moves_unking(board);
-                    if ! moves.is_empty() {
-                        return true;
-                    }
-                }
-            }
-        }
-
-        false
-    }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum PieceType {
-    Pawn = 0,
-    Rook,
-    Knight,
-    Bishop,
-    Queen,
-    King
-}
-
-pub const PIECE_LETTERS: [char; 6] = ['p','r','n','b','q','k'];
-pub const COLOR_LETTERS: [char; 3] = ['r','g','y'];
-
-#[derive(Clone, Copy, Debug)]
-pub struct Piece {
-    pub typ: PieceType,
-    pub player: Player
-}
-
-#[derive(Default, Clone, Copy, Debug)]
-pub struct Field {
-    pub coord: Coord,
-    pub typ: FieldType,
-    pub piece: Option<Piece>,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Direction {
-    ForwardRed,
-    ForwardYellow,
-    ForwardGreen,
-    RedRight,
-    RedLeft,
-    GreenRight,
-    GreenLeft,
-    YellowRight,
-    YellowLeft,
-
-    // diagonals
-    RedYellowToRed,
-    RedYellowToYellow,
-    GreenRedToRed,
-    GreenRedToGreen,
-    GreenYellowToYellow,
-    GreenYellowToGreen,
-    RedToRedYellow,
-    RedToGreenRed,
-    YellowToRedYellow,
-    YellowToGreenYellow,
-    GreenToGreenRed,
-    GreenToGreenYellow,
-}
-
-impl Direction {
-    pub fn is_straight(&self) -> bool {
-        self <= &Direction::YellowLeft
-    }
-
-    pub fn all() -> Vec<Self> {
-        let mut v = vec![];
-        for a in Self::ForwardRed as usize
-                  ..= Self::GreenToGreenYellow as usize {
-            let var: Self = unsafe {std::mem::transmute(a as u8)};
-            v.push(var);
-        }
-        v
-    }
-
-    pub fn is_opposite(&self, other: &Self) -> bool {
-        match self {
-            Self::ForwardRed => other == &Self::ForwardGreen || other == &Self::ForwardYellow,
-            Self::ForwardYellow => other == &Self::ForwardGreen || other == &Self::ForwardRed,
-            Self::ForwardGreen => other == &Self::ForwardRed || other == &Self::ForwardYellow,
-            Self::RedRight => other == &Self::RedLeft,
-            Self::RedLeft => other == &Self::RedRight,
-            Self::GreenRight => other == &Self::GreenLeft,
-            Self::GreenLeft => other == &Self::GreenRight,
-            Self::YellowRight => other == &Self::YellowLeft,
-            Self::YellowLeft => other == &Self::YellowRight,
-            _ => false,
-        }
-    }
-
-    pub fn orthogonals(&self) -> Vec<Self> {
-        Self::all().into_iter().filter(|x| x != self && x.is_straight() && ! self.is_opposite(x))
-                          .collect()
-    }
-
-    pub fn next(&self, start: &Field, board: &Board, _player: &Player) -> Option<Field> {
-        let (f, r) = match self {
-            Direction::ForwardRed => {
-                if start.coord.0 >= 'i' {
-                    return None;
-                }
-
-                if start.coord.1 == 8 || start.coord.1 == 12 {
-                    return None;
-                }
-
-                let r = if start.coord.1 == 4
-                    && start.coord.0 >= 'e' {
-                        9
-                    } else {
-                        start.coord.1 + 1
-                    };
-
-                (start.coord.0, r)
-            },
-            Direction::ForwardYellow => {
-                if start.coord.0 <= 'd' {
-                    return None;
-                }
-
-                if start.coord.1 == 8 || start.coord.1 == 1 {
-                    return None;
-                }
-
-                let r = if start.coord.1 == 9 {
-                    if start.coord.0 >= 'i' {
-                        5
-                    } else {
-                        4
-                    }
-                } else if start.coord.1 >= 9 {
-                    start.coord.1 - 1
-                } else if start.coord.1 >= 5 {
-                    start.coord.1 + 1
-                } else {
-                    start.coord.1 - 1
-                };
-
-                (start.coord.0, r)
-            },
-            Direction::ForwardGreen => {
-                if start.coord.0 >= 'e'
-                && start.coord.0 <= 'h' {
-                    return None;
-                }
-
-                if start.coord.1 == 12 || start.coord.1 == 1 {
-                    return None;
-                }
-
-                let r = if start.coord.1 == 5
-                && start.coord.0 >= 'i' {
-                    9
-                } else if start.coord.1 >= 9 {
-                    start.coord.1 + 1
-                } else {
-                    start.coord.1 - 1
-                };
-
-                (start.coord.0, r)
-            },
-            Direction::RedRight => {
-                if start.coord.1 >= 5 {
-                    return None;
-                }
-                let f = cadd(start.coord.0, 1);
-
-                (f, start.coord.1)
-            },
-            Direction::RedLeft => {
-                if start.coord.1 >= 5 {
-                    return None;
-                }
-                let f = csub(start.coord.0, 1);
-
-                (f, start.coord.1)
-            },
-            Direction::GreenRight => {
-                if start.coord.1 <= 4 {
-                    return None;
-                }
-                if start.coord.1 >= 9 {
-                    return None;
-                }
-                let f = if start.coord.0 == 'i' {
-                    'd'
-                } else {
-                    csub(start.coord.0, 1)
-                };
-
-                (f, start.coord.1)
-            },
-            Direction::GreenLeft => {
-                if start.coord.1 <= 4 {
-                    return None;
-                }
-                if start.coord.1 >= 9 {
-                    return None;
-                }
-                let f = if start.coord.0 == 'd' {
-                    'i'
-                } else {
-                    cadd(start.coord.0, 1)
-                };
-
-                (f, start.coord.1)
-            },
-            Direction::YellowRight => {
-                if start.coord.1 <= 8 {
-                    return None;
-                }
-                let f = if start.coord.0 == 'e' {
-                    'i'
-                } else if start.coord.0 >= 'i' {
-                    cadd(start.coord.0, 1)
-                } else {
-                    csub(start.coord.0, 1)
-                };
-
-                (f, start.coord.1)
-            },
-            Direction::YellowLeft => {
-                if start.coord.1 <= 8 {
-                    return None;
-                }
-
-                if start.coord.0 == 'h' {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'i' => 'e',
-                    x if x > 'i'=> csub(x, 1),
-                    a => cadd(a, 1)
-                };
-
-                (f, start.coord.1)
-            },
-            Direction::RedYellowToRed => {
-                if start.coord.0 >= 'i' ||
-                (start.coord.1 >= 5 && start.coord.1 <= 8) {
-                    return None;
-                }
-
-                if start.coord.1 == 1 {
-                    return None;
-                }
-
-                if start.coord.1 >= 9 && (start.coord.1 -
-                    (start.coord.0 as u8 - b'a' + 1) as usize
-                    >= 5) {
-                    return None;
-                }
-
-                if start.coord.1 <= 8 && start.coord.1 >
-                    (start.coord.0 as u8 - b'a' + 1) as usize {
-                    return None;
-                }
-
-                let f = csub(start.coord.0, 1);
-                let r = match start.coord.1 {
-                    9 => 4,
-                    x => x-1,
-                };
-
-                (f, r)
-            },
-            Direction::RedYellowToYellow => {
-                if start.coord.0 <= 'd' ||
-                    (start.coord.1 >= 5 && start.coord.1 <= 8) {
-                    return None;
-                }
-
-                if start.coord.1 == 12 {
-                    return None;
-                }
-
-                if start.coord.1 >= 9 && start.coord.1 <
-                    (start.coord.0 as u8 - b'a' + 1) as usize {
-                    return None;
-                }
-
-                if start.coord.1 <= 4 && (start.coord.1 +
-                    (start.coord.0 as u8 - b'a' + 1) as usize
-                    <= 8) {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'e' => 'i',
-                    x if x <= 'h' => csub(start.coord.0, 1),
-                    _  => cadd(start.coord.0, 1),
-                };
-                let r = match start.coord.1 {
-                    4 => 9,
-                    x => x+1,
-                };
-
-                (f, r)
-            },
-            Direction::GreenRedToRed => {
-                if start.coord.0 >= 'i' {
-                    return None;
-                }
-
-                if start.coord.1 == 1 {
-                    return None;
-                }
-
-                if start.coord.1 +
-                    (start.coord.0 as u8 - b'a' + 1) as usize >= 10{
-                    return None;
-                }
-
-                let f = cadd(start.coord.0, 1);
-                let r = start.coord.1 - 1;
-                (f, r)
-            },
-            Direction::GreenRedToGreen => {
-                if start.coord.1 >= 9 {
-                    return None;
-                }
-
-                if start.coord.1 == 8 {
-                    return None;
-                }
-
-                if start.coord.0 <= 'h' && (start.coord.1 <
-                    (start.coord.0 as u8 - b'a' + 1) as usize) {
-                    return None;
-                }
-
-                if start.coord.0 >= 'i'
-                    && ((start.coord.0 as u8 - b'a' + 1) as usize
-                        - start.coord.1) >= 5 {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'd' => 'i',
-                    x => cadd(x, 1),
-                };
-                let r = start.coord.1 + 1;
-                (f, r)
-            },
-            Direction::GreenYellowToYellow => {
-                if start.coord.1 <= 4 || start.coord.0 <= 'd' {
-                    return None;
-                }
-
-                if start.coord.1 == 12 {
-                    return None;
-                }
-
-                if start.coord.0 <= 'h' && (start.coord.1 -
-                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 3 {
-                    return None;
-                }
-
-                if start.coord.1 <= 8
-                    && ((start.coord.0 as u8 - b'a' + 1) as usize - start.coord.1
-                    ) <= 3 {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'i' => 'e',
-                    x if x >= 'i' => csub(x, 1),
-                    x  => cadd(x, 1),
-                };
-                let r = match start.coord.1 {
-                    5 => 9,
-                    x if x <= 8 => x - 1,
-                    x  => x + 1,
-                };
-
-                (f, r)
-            },
-            Direction::GreenYellowToGreen => {
-                if start.coord.1 <= 4 || (start.coord.0 >= 'e'
-                                          && start.coord.0 <= 'h') {
-                    return None;
-                }
-
-                if start.coord.1 == 8 {
-                    return None;
-                }
-
-                if start.coord.0 <= 'd' && (start.coord.1 +
-                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 8 {
-                    return None;
-                }
-
-                if start.coord.0 >= 'i'
-                    && ((start.coord.0 as u8 - b'a' + 1) as usize) < start.coord.1 {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'i' => 'd',
-                    x  => csub(x, 1),
-                };
-                let r = match start.coord.1 {
-                    9 => 5,
-                    x if x > 9 => x - 1,
-                    x => x + 1,
-                };
-
-                (f, r)
-            },
-            Direction::RedToRedYellow => {
-                if start.coord.0 >= 'i' || (start.coord.1 >= 5
-                                          && start.coord.1 <= 8) {
-                    return None;
-                }
-
-                if start.coord.0 == 'h' {
-                    return None;
-                }
-
-                if start.coord.0 <= 'd' && (start.coord.1 >
-                    (start.coord.0 as u8 - b'a' + 1) as usize) {
-                    return None;
-                }
-
-                if start.coord.1 >= 9
-                    && (start.coord.1 - (start.coord.0 as u8 - b'a' + 1) as usize) >= 5 {
-                    return None;
-                }
-
-                let f = cadd(start.coord.0, 1);
-                let r = match start.coord.1 {
-                    4 => 9,
-                    x => x + 1,
-                };
-
-                (f, r)
-            },
-            Direction::RedToGreenRed => {
-                if start.coord.1 >= 9 || start.coord.0 >= 'i' {
-                    return None;
-                }
-
-                if start.coord.0 == 'a' {
-                    return None;
-                }
-
-                if start.coord.1 >= 5 && (start.coord.1 +
-                    (start.coord.0 as u8 - b'a' + 1) as usize) >= 10 {
-                    return None;
-                }
-
-                if start.coord.0 >= 'e'
-                    && (start.coord.1 + (start.coord.0 as u8 - b'a' + 1) as usize) >= 10 {
-                    return None;
-                }
-
-                let f = csub(start.coord.0, 1);
-                let r = start.coord.1 + 1;
-
-                (f, r)
-            },
-            Direction::YellowToRedYellow => {
-                if (start.coord.1 <= 8
-                    && start.coord.1 >= 5) || start.coord.0 <= 'd' {
-                    return None;
-                }
-
-                if start.coord.0 == 'h' {
-                    return None;
-                }
-
-                if start.coord.1 <= 4 && (start.coord.1 +
-                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 8 {
-                    return None;
-                }
-
-                if start.coord.0 >= 'i'
-                    && start.coord.1 < (start.coord.0 as u8 - b'a' + 1) as usize {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'i' => 'e',
-                    x if x >= 'i' => csub(x, 1),
-                    x => cadd(x, 1),
-                };
-                let r = match start.coord.1 {
-                    9 => 4,
-                    x => x - 1,
-                };
-
-                (f, r)
-            },
-            Direction::YellowToGreenYellow => {
-                if start.coord.0 <= 'd'
-                    || start.coord.1 <= 4 {
-                    return None;
-                }
-
-                if start.coord.0 == 'l' {
-                    return None;
-                }
-
-                if start.coord.0 <= 'h' && (start.coord.1 -
-                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 3 {
-                    return None;
-                }
-
-                if start.coord.1 <= 8
-                    && ((start.coord.0 as u8 - b'a' + 1) as usize - start.coord.1) <= 3 {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'e' => 'i',
-                    x if x >= 'i' => cadd(x, 1),
-                    x => csub(x, 1),
-                };
-                let r = match start.coord.1 {
-                    9 => 5,
-                    x if x >= 9 => x - 1,
-                    x => x + 1,
-                };
-
-                (f, r)
-            },
-            Direction::GreenToGreenRed => {
-                if start.coord.1 >= 9 || (
-                    start.coord.0 <= 'h' &&  start.coord.0 >= 'e') {
-                    return None;
-                }
-
-                if start.coord.0 == 'a' {
-                    return None;
-                }
-
-                if start.coord.1 <= 4 && start.coord.1 <
-                    (start.coord.0 as u8 - b'a' + 1) as usize {
-                    return None;
-                }
-
-                if start.coord.0 >= 'i'
-                    && ((start.coord.0 as u8 - b'a' + 1) as usize
-                    - start.coord.1) >= 5 {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'i' => 'd',
-                    x => csub(x, 1),
-                };
-                let r = start.coord.1 - 1;
-                (f, r)
-            },
-            Direction::GreenToGreenYellow => {
-                if (start.coord.0 <= 'h' && start.coord.0 >= 'e')
-                    || start.coord.1 <= 4 {
-                    return None;
-                }
-
-                if start.coord.0 == 'l' {
-                    return None;
-                }
-
-                if start.coord.0 <= 'd' && (start.coord.1 +
-                    (start.coord.0 as u8 - b'a' + 1) as usize) <= 8 {
-                    return None;
-                }
-
-                if start.coord.1 >= 9
-                    && ((start.coord.0 as u8 - b'a' + 1) as usize)
-                        < start.coord.1 {
-                    return None;
-                }
-
-                let f = match start.coord.0 {
-                    'd' => 'i',
-                    x => cadd(x, 1),
-                };
-                let r = match start.coord.1 {
-                    5 => 9,
-                    x if x >= 9 => x + 1,
-                    x => x - 1,
-                };
-
-                (f, r)
-            },
-        };
-
-        board.get_field(f, r).cloned()
-    }
-}
-
-impl Field {
-    pub fn new(a: char, b: usize, tp: FieldType) -> Self {
-        Self {
-            coord: Coord(a, b),
-            typ: tp,
-            piece: None,
-        }
-    }
-
-    fn get_pawn_dirs(&self, player: Player) -> Vec<Direction> {
-        vec![match player {
-            Player::Red => {
-                if (self.coord.0 >= 'e' && self.coord.0 <= 'h') ||
-                    (self.coord.0 >= 'a' && self.coord.0 <= 'd') {
-                    Direction::ForwardRed
-                } else if self.coord.1 <= 8 {
-                    Direction::ForwardYellow
-                } else {
-                    Direction::ForwardGreen
-                }
-            },
-            Player::Green => {
-                if (self.coord.0 >= 'a' && self.coord.0 <= 'd') ||
-                    (self.coord.0 >= 'i') {
-                    Direction::ForwardGreen
-                } else if self.coord.1 <= 4 {
-                    Direction::ForwardYellow
-                } else {
-                    Direction::ForwardRed
-                }
-            },
-            Player::Yellow => {
-                if (self.coord.0 >= 'e' && self.coord.0 <= 'h') ||
-                    (self.coord.0 >= 'i') {
-                    Direction::ForwardYellow
-                } else if self.coord.1 <= 4 {
-                    Direction::ForwardGreen
-                } else {
-                    Direction::ForwardRed
-                }
-            },
-        }]
-    }
-
-    fn get_possible_moves_unchecked(&self, board: &Board) -> Vec<Coord> {
-        let piece = self.piece.unwrap();
-        let player = piece.player;
-        let _fields = board.get_fields();
-
-        let _f = Board::get_fields(board);
-
-        const STRAIGHT_DIRS: [Direction; 9] = [Direction::ForwardRed, Direction::ForwardYellow,
-                                               Direction::ForwardGreen,
-                                               Direction::RedRight, Direction::RedLeft,
-                                               Direction::GreenRight, Direction::GreenLeft,
-                                               Direction::YellowRight, Direction::YellowLeft,
-        ];
-        const DIAGONAL_DIRS: [Direction; 12] = [Direction::RedYellowToRed, Direction::RedYellowToYellow,
-                                                Direction::GreenRedToRed, Direction::GreenRedToGreen,
-                                                Direction::GreenYellowToYellow, Direction::GreenYellowToGreen,
-
-                                                Direction::RedToRedYellow, Direction::RedToGreenRed,
-                                                Direction::YellowToRedYellow, Direction::YellowToGreenYellow,
-                                                Direction::GreenToGreenYellow, Direction::GreenToGreenRed,
-        ];
-
-        match piece.typ {
-            PieceType::Pawn => {
-                let move_dirs = self.get_pawn_dirs(player);
-
-                let capture_dirs = match player {
-                    Player::Red => [Direction::RedToGreenRed, Direction::RedToRedYellow,
-                                    Direction::GreenYellowToGreen, Direction::GreenRedToGreen,
-                                    Direction::GreenYellowToYellow, Direction::RedYellowToYellow],
-
-                    Player::Green => [Direction::GreenToGreenRed, Direction::GreenToGreenYellow,
-                                    Direction::RedYellowToRed, Direction::GreenRedToGreen,
-                                    Direction::RedYellowToYellow, Direction::GreenYellowToYellow],
-
-                    Player::Yellow => [Direction::YellowToGreenYellow, Direction::YellowToRedYellow,
-                                    Direction::GreenRedToGreen, Direction::RedYellowToRed,
-                                    Direction::GreenRedToRed, Direction::GreenYellowToGreen],
-                };
-
-                let is_at_home = match player {
-                    Player::Red => self.coord.1 == 2,
-                    Player::Green => self.coord.1 == 7,
-                    Player::Yellow => self.coord.1 == 11,
-                };
-
-                let mut fields = vec![];
-                for capture_dir in capture_dirs {
-                    if let Some(a) = capture_dir.next(self, board, &player) {
-                        if let Some(p) = a.piece {
-                            if p.player != player {
-                                fields.push(a.coord);
-                            }
-                        }
-                    }
-                }
-
-
-                for move_dir in move_dirs {
-                    if let Some(a) = move_dir.next(self, board, &player) {
-                        if a.piece.is_none() {
-                            fields.push(a.coord);
-
-                            if is_at_home {
-                                if let Some(a) = move_dir.next(&a, board, &player) {
-                                    if a.piece.is_none() {
-                                        fields.push(a.coord);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-
-                fields
-            },
-            PieceType::Rook | PieceType::Bishop |
-            PieceType::Queen => {
-                let dirs = match piece.typ {
-                    PieceType::Rook => STRAIGHT_DIRS.to_vec(),
-                    PieceType::Queen => vec![STRAIGHT_DIRS.to_vec(),
-                                              DIAGONAL_DIRS.to_vec()]
-                        .into_iter().flatten().collect(),
-                    PieceType::Bishop => DIAGONAL_DIRS.to_vec(),
-                    _ => unreachable!("impossible"),
-                };
-
-                let mut fields = vec![];
-                for direction in dirs {
-                    let mut field = Some(*self);
-                    while {field = direction.next(&field.unwrap(), board, &player);
-                            field.is_some()} {
-                        if field.is_none() {
-                            break;
-                        }
-
-                        if let Some(p) = field.unwrap().piece {
-                            if p.player != player {
-                                fields.push(field.unwrap().coord);
-                            }
-                            break;
-                        }
-
-                        fields.push(field.unwrap().coord);
-                    }
-                }
-
-                fields
-            },
-            PieceType::Knight => {
-                let mut fields = vec![];
-
-                // 1 - 2
-                for dir in STRAIGHT_DIRS {
-                    let f1 = dir.next(self, board, &player);
-                    for orth in dir.orthogonals() {
-                        let f3 = f1.and_then(|f| orth.next(&f, board, &player))
-                            .and_then(|f| orth.next(&f, board, &player));
-                        if let Some(f) = f3 {
-                            if let Some(p) = f.piece {
-                                if p.player == player {
-                                    continue;
-                                }
-                            }
-                            fields.push(f.coord);
-                        }
-                    }
-                }
-
-                // 2 - 1
-                for dir in STRAIGHT_DIRS {
-                    let f2 = dir.next(self, board, &player)
-                        .and_then(|f| dir.next(&f, board, &player));
-                    for orth in dir.orthogonals() {
-                        let f3 = f2.and_then(|f| orth.next(&f, board, &player));
-                        if let Some(f) = f3 {
-                            if let Some(p) = f.piece {
-                                if p.player == player {
-                                    continue;
-                                }
-                            }
-                            fields.push(f.coord);
-                        }
-                    }
-                }
-                fields
-            }
-            PieceType::King => {
-                let mut dirs = STRAIGHT_DIRS.to_vec();
-                dirs.append(&mut DIAGONAL_DIRS.to_vec());
-                let mut fields = vec![];
-                for d in dirs {
-                    let field = d.next(self, board, &player);
-                    if let Some(f) = field {
-                        if let Some(p) = f.piece {
-                            if p.player == player {
-                                continue;
-                            }
-                        }
-                        fields.push(f.coord);
-                    }
-                }
-                fields
-            },
-        }
-    }
-
-    fn get_possible_moves_unking(&self, board: &Board) -> (Vec<Coord>, Vec<Coord>) {
-        let moves = self.get_possible_moves_unchecked(board)
-            .into_iter().filter(|x| {
-                let mut new_board = board.clone();
-                new_board.get_field_mut(x.0, x.1)
-                    .unwrap().piece =
-                    self.piece;
-
-                new_board.get_field_mut(self.coord.0, self.coord.1)
-                    .unwrap().piece =
-                    None;
-
-                ! new_board.is_check(self.piece.unwrap().player)
-        }).collect::<Vec<_>>();
-
-        let mut king_capt_moves = vec![];
-        for mov in &moves {
-            let f = board.get_field(mov.0, mov.1).unwrap();
-            if let Some(p) = f.piece {
-                if p.player != self.piece.unwrap().player &&
-                    p.typ == PieceType::King {
-                        king_capt_moves.push(*mov);
-                }
-            }
-        }
-
-        (moves, king_capt_moves)
-    }
-
-    fn get_possible_moves(&self, board: &Board) -> Vec<Coord> {
-        let (moves, king_capt_moves) = self.get_possible_moves_unking(board);
-        if king_capt_moves.is_empty() && self.piece.unwrap().player.can_capture_king(board) {
-            vec![]
-        } else if king_capt_moves.is_empty() {
-            moves
-        } else {
-            king_capt_moves
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct Section {
-    pub fields: [[Field; 4]; 4],
-    pub start_file: char,
-    pub start_rank: usize,
-    points: fn (i32, i32) -> [(i32,i32); 4],
-}
-
-fn cadd(c: char, i: usize) -> char {
-    (c as u8 + i as u8) as char
-}
-
-fn csub(c: char, i: usize) -> char {
-    (c as u8 - i as u8) as char
-}
-
-impl Section {
-    pub fn new(f: char, r: usize, inverse_colors: bool,
-               pts: fn(i32,i32)->[(i32,i32);4]) -> Self {
-        let tp1 = if inverse_colors { FieldType::BLACK  }
-        else { FieldType::WHITE };
-
-        let tp2 = if ! inverse_colors { FieldType::BLACK  }
-        else { FieldType::WHITE };
-
-        Self {
-            points: pts,
-            fields: [[
-                Field::new(f, r, tp2),
-                Field::new(f, r+1, tp1),
-                Field::new(f, r+2, tp2),
-                Field::new(f, r+3, tp1),
-            ],[
-                Field::new(cadd(f, 1), r, tp1),
-                Field::new(cadd(f, 1), r+1, tp2),
-                Field::new(cadd(f, 1), r+2, tp1),
-                Field::new(cadd(f, 1), r+3, tp2),
-            ],[
-                Field::new(cadd(f, 2), r, tp2),
-                Field::new(cadd(f, 2), r+1, tp1),
-                Field::new(cadd(f, 2), r+2, tp2),
-                Field::new(cadd(f, 2), r+3, tp1),
-            ],[
-                Field::new(cadd(f, 3), r, tp1),
-                Field::new(cadd(f, 3), r+1, tp2),
-                Field::new(cadd(f, 3), r+2, tp1),
-                Field::new(cadd(f, 3), r+3, tp2),
-            ]],
-            start_file: f,
-            start_rank: r,
-        }
-    }
-
-    fn get_radius_and_height(&self, ww: i32, wh: i32) -> (i32, i32) {
-        let radius: i32 = ((ww.min(wh) / 2) as f32 * 0.9) as _;
-        let height: i32 = ((3f32.sqrt() * radius as f32) / 2.0) as i32;
-
-        (radius, height)
-    }
-
-    fn get_coords(&self, x: usize, y: usize, ww: i32, mut wh: i32) -> [(i32, i32); 4] {
-        let (radius, height) = self.get_radius_and_height(ww, wh);
-
-
-        let offx = (ww - 2 * radius) / 2;
-        let offy = (wh - 2 * height) / 2;
-
-//        ww -= offx;
-        wh -= offy;
-
-        let pts = (self.points)(radius, height);
-
-        let y0 = wh - pts[0].1;
-        let y1 = wh - pts[1].1;
-        let y2 = wh - pts[2].1;
-        let y3 = wh - pts[3].1;
-
-        let x0 = offx + pts[0].0;
-        let x1 = offx + pts[1].0;
-        let x2 = offx + pts[2].0;
-        let x3 = offx + pts[3].0;
-        let mut coords = [(0, 0); 4];
-
-        for a in 0 .. 2 {
-            for b in 0 .. 2 {
-                let xfrac = (x + a) as f32 / 4.0;
-                let yfrac = (y + b) as f32 / 4.0;
-
-                let xo0 = x0 as f32 + (x1 - x0) as f32 * xfrac;
-                let xo1 = x3 as f32 + (x2 - x3) as f32 * xfrac;
-                let rx = xo0 + (xo1 - xo0) * yfrac;
-
-                let yo0 = y0 as f32 + (y3 - y0) as f32 * yfrac;
-                let yo1 = y1 as f32 + (y2 - y1) as f32 * yfrac;
-                let ry = yo0 + (yo1 - yo0) * xfrac;
-
-                coords[2 * a as usize + b as usize] = (rx as i32, ry as i32);
-            }
-        }
-
-        coords
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Board {
-    sections: [Section; 6],
-    current_player: Player,
-    active_field: Option<Field>,
-}
-
-impl Default for Board {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Board {
-    pub fn new() -> Self {
-        let s = [
-            Section::new('a', 1, false,
-            |r, h| [(r/2, 0), (r, 0), (r, h), (r/4, h/2)]),
-            Section::new('e', 1, false,
-            |r, h| [(r, 0), (r+r/2, 0), (r+3*r/4, h/2), (r, h)]),
-            Section::new('e', 9, false,
-            |r, h| [(r, h), (r+3*r/4, h/2), (r*2, h), (r+3*r/4, h+h/2)]),
-            Section::new('i', 9, true,
-            |r, h| [(r, h), (r, h*2),(r+r/2, h*2), (r+3*r/4, h+h/2)]),
-            Section::new('i', 5, false,
-            |r, h| [(r, h), (r, h*2), (r/2, h*2), (r/4, h+h/2)]),
-            Section::new('a', 5, false,
-            |r, h| [(r/4, h/2), (r, h), (r/4, h+h/2), (0, h)]),
-        ];
-        Board {
-            sections: s,
-            current_player: Player::Red,
-            active_field: None,
-        }
-    }
-
-    pub fn get_fields(&self) -> Vec<Field> {
-        self.sections.iter().flat_map(|x| x.fields
-                         .into_iter()
-                         .collect::<Vec<_>>()
-                         ).flatten()
-                          .collect::<Vec<_>>()
-    }
-
-    pub fn get_possible_move_targets_unchecked(&self, _player: Player) -> Vec<Coord> {
-        let fields = self.get_fields();
-        let mut targets = vec![];
-        for f in fields {
-            if f.piece.is_none() {
-                continue;
-            }
-            let mut moves = f.get_possible_moves_unchecked(self);
-            targets.append(&mut moves);
-        }
-        targets
-    }
-
-    pub fn get_king_field(&self, player: Player) -> Option<Field> {
-        let fields = self.get_fields();
-        for f in fields {
-            if let Some(p) = f.piece {
-                if p.player == player && p.typ == PieceType::King {
-                    return Some(f);
-                }
-            }
-        }
-        None
-    }
-
-    pub fn is_check(&self, player: Player) -> bool {
-        let king = self.get_king_field(player);
-        let king = match king {
-            Some(k) => k,
-            None => return false,
-        };
-        let moves = self.get_possible_move_targets_unchecked(player);
-
-        moves.contains(&king.coord)
-    }
-
-    fn get_section_and_field(&self, file: char, rank: usize) -> Option<(Section, &Field)> {
-        for s in &self.sections {
-            let sect = *s;
-            for r in &s.fields {
-                for f in r.iter() {
-                    if f.coord.0 == file
-                        && f.coord.1 == rank {
-                            return Some((sect, f));
-                        }
-                }
-            }
-        }
-
-        None
-    }
+#[cfg(all(not(target_family = "wasm"), feature = "sdl", not(feature = "terminal")))]
+mod sdl_backend;
 
-    fn get_section_and_field_mut(&mut self, file: char, rank: usize) -> Option<(Section, &mut Field)> {
-        for s in &mut self.sections {
-            let sect = *s;
-            for r in &mut s.fields {
-                for f in r.iter_mut() {
-                    if f.coord.0 == file
-                        && f.coord.1 == rank {
-                            return Some((sect, f));
-                        }
-                }
-            }
-        }
+#[cfg(all(not(target_family = "wasm"), feature = "terminal"))]
+mod terminal_backend;
 
-        None
-    }
+#[cfg(target_family = "wasm")]
+mod wasm_backend;
 
-    fn get_field(&self, file: char, rank: usize) -> Option<&Field> {
-        self.get_section_and_field(file, rank).map(|(_, f)| f)
-    }
+#[cfg(not(target_family = "wasm"))]
+mod image_backend;
 
-    fn get_field_mut(&mut self, file: char, rank: usize) -> Option<&mut Field> {
-        self.get_section_and_field_mut(file, rank).map(|(_, f)| f)
-    }
+use crate::backend::Backend;
+use std::time::Duration;
 
-    pub fn get_coords(&mut self, coord: Coord, ww: i32, wh: i32) -> Option<[(i32, i32); 4]> {
-        let (sect, _) = self.get_section_and_field(coord.0, coord.1)?;
+#[cfg(all(not(target_family = "wasm"), feature = "sdl", not(feature = "terminal")))]
+type BackendType = sdl_backend::SdlBackend<'static, 'static>;
 
-        for a in 0 .. 4 {
-            for b in 0 .. 4 {
-                if sect.fields[a][b].coord == coord {
-                    return Some(sect.get_coords(a, b, ww, wh));
-                }
-            }
-        }
-        None
-    }
+#[cfg(all(not(target_family = "wasm"), feature = "terminal"))]
+type BackendType = terminal_backend::TerminalBackend;
 
-    fn place_pieces_half(&mut self, rank: usize,
-                         start_file: char, mut invf: bool, invr: bool,
-                         right: bool, player: Player) {
+#[cfg(all(not(target_family = "wasm"), not(feature = "sdl"), not(feature = "terminal")))]
+compile_error!("enable the `sdl` or the `terminal` feature to get a frontend");
 
-        if right {
-            invf ^= true;
-        }
+#[cfg(target_family = "wasm")]
+type BackendType = wasm_backend::WasmBackend;
 
-        self.get_field_mut(cadd(start_file,
-                            if invf {3} else {0}), rank).unwrap()
-                                .piece = Some(Piece {
-                                    typ: PieceType::Rook,
-                                    player,
-                                });
-
-        self.get_field_mut(cadd(start_file, if invf {0} else {3}), rank).unwrap()
-                                .piece = Some(Piece {
-                                    typ: if right {PieceType::King} else {PieceType::Queen},
-                                    player,
-                                });
-
-        self.get_field_mut(cadd(start_file, if invf {2} else {1}), rank).unwrap()
-                                .piece = Some(Piece {
-                                    typ: PieceType::Knight,
-                                    player,
-                                });
-        self.get_field_mut(cadd(start_file, if invf {1} else {2}), rank).unwrap()
-                                .piece = Some(Piece {
-                                    typ: PieceType::Bishop,
-                                    player,
-                                });
-
-        for a in 0 .. 4 {
-            self.get_field_mut(cadd(start_file,
-                a), if invr {rank-1} else {rank+1}) .unwrap()
-                        .piece = Some(Piece {
-                            typ: PieceType::Pawn,
-                            player,
-                        });
-        }
+#[cfg(not(target_family = "wasm"))]
+type ConnectionType = threechess::Client;
 
-    }
+#[cfg(target_family = "wasm")]
+type ConnectionType = wasm_backend::WebSocketClient;
 
-    pub fn place_pieces(&mut self) {
-        self.place_pieces_half( 1, 'a', false, false, false, Player::Red);
-        self.place_pieces_half( 1, 'e', false, false, true,  Player::Red);
+#[cfg(target_family = "wasm")]
+macro_rules! println {
+     ($($arg:tt)*) => {
+         BackendType::log(::std::format!($($arg)*));
+     };
+ }
 
-        self.place_pieces_half( 8, 'i', true,  true, false, Player::Green);
-        self.place_pieces_half( 8, 'a', true,  true, true, Player::Green);
+#[cfg(target_family = "wasm")]
+macro_rules! eprintln {
+     ($($arg:tt)*) => {
+         BackendType::elog(::std::format!($($arg)*));
+     };
+ }
 
-        self.place_pieces_half(12, 'e', true,  true, false, Player::Yellow);
-        self.place_pieces_half(12, 'i', false,  true, true,  Player::Yellow);
-    }
-}
+use backend::{Color, Event, ImageHandle, Key, MouseButton};
+use threechess::clock::format_time;
+use threechess::{Ai, Board, ClientMessage, Clocks, Coord, FieldType, GameOutcome, GameRecord, Move,
+                 Player, PlayerStatus, Rules, ServerMessage, TimeControl, Variant,
+                 COLOR_LETTERS, PIECE_LETTERS};
 
 fn draw_polygon<B: backend::Backend>
     (backend: &mut B, points: &[(i32, i32)]) {
@@ -1258,42 +82,17 @@ fn point_is_in_quadrilateral(pt: (i32, i32), points: &[(i32, i32); 4]) -> bool {
     point_is_in_triangle(pt, &[points[1], points[2], points[3]])
 }
 
-fn fill_quadrilateral(backend: &mut BackendType, points: &[(i32, i32); 4]) {
-    let lx = points[0].0
-        .min(points[1].0)
-        .min(points[2].0)
-        .min(points[3].0);
-
-    let rx = points[0].0
-        .max(points[1].0)
-        .max(points[2].0)
-        .max(points[3].0);
-
-    let ly = points[0].1
-        .min(points[1].1)
-        .min(points[2].1)
-        .min(points[3].1);
-
-    let ry = points[0].1
-        .max(points[1].1)
-        .max(points[2].1)
-        .max(points[3].1);
-
-    for y in ly ..= ry {
-        for x in lx ..= rx {
-            if point_is_in_quadrilateral((x, y), points) {
-                backend.draw_point(x, y);
-            }
-        }
-    }
+fn fill_quadrilateral<B: backend::Backend>(backend: &mut B, points: &[(i32, i32); 4]) {
+    // the corners come two per side, not in order around the field
+    backend.fill_polygon(&[points[0], points[2], points[3], points[1]]);
 }
 
 #[export_name = "main_loop_step"]
 pub extern "C" fn main_loop_step() -> bool {
-    let board = unsafe{__BOARD.as_mut().unwrap()};
-    let textures = unsafe{__TEXTURES.as_ref().unwrap()};
-    let backend = unsafe{__BACKEND.as_mut().unwrap()};
-    let mate = unsafe{__MATE.as_mut().unwrap()};
+    let State {
+        board, textures, backend, active_field, promotion, record_saved, redo, drag, computer, ai,
+        net, clocks,
+    } = state();
 
     backend.set_draw_color(Color::WHITE);
     backend.clear();
@@ -1301,137 +100,335 @@ pub extern "C" fn main_loop_step() -> bool {
     let ww = backend.win_size().0 as i32;
     let wh = backend.win_size().1 as i32;
 
+    if let Some(n) = net {
+        if ! receive(board, n, clocks, Duration::from_millis(backend.ticks())) {
+            return false;
+        }
+    }
+
     for e in backend.poll_event() {
         match e {
             Event::Quit => return false,
+            Event::MouseButtonDown(mouse_btn, x, y) => {
+                if mouse_btn != MouseButton::Left || promotion.is_some()
+                    || ! human_turn(board, computer, net) {
+                    continue;
+                }
+                if let Some(f) = field_at(board, x, y, ww, wh) {
+                    let own = board.get_field(f.0, f.1).unwrap().piece
+                        .is_some_and(|p| p.player == board.current_player());
+                    if own {
+                        *active_field = Some(f);
+                        *drag = Some(Drag { from: f, start: (x, y), pos: (x, y), moved: false });
+                    }
+                }
+            },
+            Event::MouseMotion(x, y) => {
+                if let Some(d) = drag {
+                    d.pos = (x, y);
+                    let (dx, dy) = (x - d.start.0, y - d.start.1);
+                    d.moved |= dx * dx + dy * dy > DRAG_THRESHOLD * DRAG_THRESHOLD;
+                }
+            },
             Event::MouseButtonUp(mouse_btn, x, y) => {
                 if mouse_btn == MouseButton::Left {
-                    let mut pressed_field = None;
-                    'out: for s in &board.sections {
-                        for yi in 0..4 {
-                            for xi in 0..4 {
-                                let coords = s.get_coords(xi, yi, ww, wh);
-                                if point_is_in_quadrilateral((x, y), &coords) {
-                                    pressed_field = Some(s.fields[xi][yi].coord);
-                                    break 'out;
-                                }
+                    if let Some(d) = drag.take() {
+                        // releasing without moving leaves the piece selected,
+                        // so it can still be moved with a second click
+                        if d.moved {
+                            *active_field = None;
+                            if let Some(f) = field_at(board, x, y, ww, wh) {
+                                try_move(board, d.from, f, promotion, redo, net);
+                            }
+                        }
+                        continue;
+                    }
+
+                    let seats = seat_button_rects(backend, computer, ww, wh);
+                    if let Some(i) = seats.iter().position(|r| rect_contains(*r, x, y))
+                        .filter(|_| net.is_none()) {
+                        computer[i] = ! computer[i];
+                        *active_field = None;
+                        *promotion = None;
+                        continue;
+                    }
+
+                    let buttons = button_rects(backend, wh);
+                    if let Some(i) = buttons.iter().position(|r| rect_contains(*r, x, y))
+                        .filter(|_| net.is_none()) {
+                        *active_field = None;
+                        *promotion = None;
+                        match BUTTONS[i] {
+                            "undo" => undo(board, redo, record_saved, computer, clocks),
+                            "redo" => redo_move(board, redo, computer, clocks),
+                            _ => unreachable!(),
+                        }
+                        continue;
+                    }
+
+                    // spectators, and everyone while the computer or a remote
+                    // player is to move, can only look at the board
+                    if ! human_turn(board, computer, net) {
+                        continue;
+                    }
+
+                    if let Some(moves) = promotion.take() {
+                        let rects = promotion_picker_rects(ww, wh);
+                        for (mov, rect) in moves.into_iter().zip(rects) {
+                            if rect_contains(rect, x, y) {
+                                play(board, mov, redo, net);
                             }
                         }
+                        continue;
                     }
 
-                    if let Some(f) = pressed_field {
+                    if let Some(f) = field_at(board, x, y, ww, wh) {
                         let pf = board.get_field(f.0, f.1).unwrap();
-                        if board.active_field.is_some() &&
-                            ! (pf.piece.map_or(false, |x| x
-                                               .player == board.current_player)) {
-                                let af = board.active_field.unwrap();
-
-                                let possible_moves = af.get_possible_moves(&board);
-                                if ! possible_moves.contains(&f) {
-                                    board.active_field = None;
-                                    continue;
+                        if active_field.is_some() &&
+                            ! pf.piece.is_some_and(|x| x.player == board.current_player()) {
+                                let af = active_field.take().unwrap();
+                                try_move(board, af, f, promotion, redo, net);
+                            } else if let Some(p) = pf.piece {
+                                if p.player == board.current_player() {
+                                    *active_field = Some(f);
                                 }
+                            }
+                    }
+                }
+            },
+            Event::KeyDown(key, modifiers) => {
+                match (key, modifiers.ctrl) {
+                    (Key::Char('z'), true) | (Key::Char('u'), false) if net.is_none() => {
+                        undo(board, redo, record_saved, computer, clocks);
+                    },
+                    (Key::Char('y'), true) | (Key::Char('r'), false) if net.is_none() => {
+                        redo_move(board, redo, computer, clocks);
+                    },
+                    (Key::Escape, _) => (),
+                    _ => continue,
+                }
+                *active_field = None;
+                *promotion = None;
+                *drag = None;
+            },
+            Event::KeyUp(..) => (),
+            Event::TextInput(text) => {
+                match text.trim() {
+                    "" => continue,
+                    "quit" | "exit" => return false,
+                    "undo" | "redo" if net.is_some() => {
+                        eprintln!("moves cannot be taken back in a network game");
+                    },
+                    "resign" | "draw" if net.is_some() => {
+                        eprintln!("the game cannot be ended early in a network game");
+                    },
+                    "undo" => undo(board, redo, record_saved, computer, clocks),
+                    "redo" => redo_move(board, redo, computer, clocks),
+                    _ if board.outcome().is_some() => {
+                        eprintln!("the game is over");
+                    },
+                    "draw" if computer.iter().any(|&c| c) => {
+                        eprintln!("the computer does not agree to a draw");
+                    },
+                    // everyone left plays at this screen, so they all agree
+                    "draw" => {
+                        board.agree_draw();
+                        redo.clear();
+                    },
+                    "resign" if ! human_turn(board, computer, net) => {
+                        eprintln!("it is not your turn");
+                    },
+                    "resign" => {
+                        let player = board.current_player();
+                        board.eliminate(player, PlayerStatus::Resigned);
+                        redo.clear();
+                    },
+                    _ if net.as_ref().is_some_and(|n| n.seat.is_none()) => {
+                        eprintln!("spectators cannot move");
+                    },
+                    _ if ! human_turn(board, computer, net) => {
+                        eprintln!("it is not your turn");
+                    },
+                    text => match board.parse_move(text) {
+                        Ok(mov) => play(board, mov, redo, net),
+                        Err(e) => { eprintln!("{e}"); },
+                    },
+                }
+                *active_field = None;
+                *promotion = None;
+                *drag = None;
+            },
+        }
+    }
 
-                                let mut moving_piece = board.active_field.unwrap().piece;
+    // in a network game the server runs the clocks
+    let now = Duration::from_millis(backend.ticks());
+    if let Some(c) = clocks.as_mut().filter(|_| net.is_none()) {
+        run_clocks(board, c, now);
+    }
 
-                                if moving_piece.unwrap().typ == PieceType::Pawn {
-                                    let at_end = match board.current_player {
-                                        Player::Red => f.1 == 8 || f.1 == 12,
-                                        Player::Green => f.1 == 1 || f.1 == 12,
-                                        Player::Yellow => f.1 == 8 || f.1 == 1,
-                                    };
+    if board.outcome().is_some() && ! *record_saved {
+        save_record(backend, board);
+        *record_saved = true;
+    }
 
-                                    if at_end {
-                                        moving_piece.as_mut().unwrap().typ = PieceType::Queen;
-                                    }
-                                }
+    draw_status(backend, board, clocks.as_ref().map(|c| (c, now)), ww);
+    draw_fields(backend, board, ww, wh);
 
-                                let f = board.get_field_mut(f.0, f.1).unwrap();
-                                f.piece = moving_piece;
+    if let Some(f) = *active_field {
+        let active_fields = board.get_possible_moves(f);
+        let coords: Vec<_> = active_fields.iter().flat_map(|f| board.get_coords(*f, ww, wh)).collect();
 
-                                let af = af.coord;
-                                let mut_field = board.get_field_mut(af.0, af.1).unwrap();
+        for points in coords {
+            backend.set_draw_color(
+                [Color::RED, Color::GREEN, Color::YELLOW][board.current_player() as usize]
+            );
+            let mx = (points[0].0 + points[1].0 + points[2].0 + points[3].0) / 4;
+            let my = (points[0].1 + points[1].1 + points[2].1 + points[3].1) / 4;
 
-                                mut_field.piece = None;
-                                board.active_field = None;
+            backend.fill_circle((mx, my), ww.min(wh) / 40);
+        }
+    }
 
-                                board.current_player = board.current_player.next();
-                                while mate[&board.current_player] {
-                                    board.current_player = board.current_player.next();
-                                }
+    let dragged = drag.as_ref().filter(|d| d.moved).map(|d| d.from);
+    draw_pieces(backend, board, textures, dragged, ww, wh);
 
-                                if board.current_player.is_mate(&board) {
-                                    mate.insert(board.current_player, true);
-                                    board.current_player = board.current_player.next();
-                                }
-                            } else {
-                                if let Some(p) = pf.piece {
-                                    if p.player == board.current_player {
-                                        board.active_field = Some(*pf);
-                                    }
-                                }
-                            }
-                    }
-                }
-            },
-            //                _ => (),
+    if let Some(d) = drag.as_ref().filter(|d| d.moved) {
+        let p = board.get_field(d.from.0, d.from.1).unwrap().piece.unwrap();
+        let (_, height) = board.sections()[0].get_radius_and_height(ww, wh);
+        let w = height / 6;
+        backend.draw_image(textures[p.player as usize][p.typ as usize],
+                           d.pos.0 - w / 2, d.pos.1 - w / 2, w, w);
+    }
+
+    let buttons = if net.is_none() { button_rects(backend, wh) } else { vec![] };
+    for (text, (x, y, w, h)) in BUTTONS.iter().zip(buttons) {
+        let frame = [(x, y), (x, y + h), (x + w, y), (x + w, y + h)];
+        backend.set_draw_color(Color::BLACK);
+        draw_polygon(backend, &frame);
+
+        let (tw, th) = backend.text_size(text);
+        backend.render_text(text, x + (w - tw as i32) / 2, y + (h - th as i32) / 2,
+                            Color(0x22, 0x22, 0x22));
+    }
+
+    if let Some(n) = net {
+        let text = match n.seat {
+            Some(seat) => format!("{seat} at {}", n.address),
+            None => format!("watching {}", n.address),
+        };
+        let (tw, th) = backend.text_size(&text);
+        backend.render_text(&text, ww - tw as i32 - 10, wh - th as i32 - 10, Color(0x22, 0x22, 0x22));
+    }
+
+    let seats = if net.is_none() { seat_button_rects(backend, computer, ww, wh) } else { vec![] };
+    for (p, (x, y, w, h)) in Player::ALL.into_iter().zip(seats) {
+        let frame = [(x, y), (x, y + h), (x + w, y), (x + w, y + h)];
+        backend.set_draw_color(Color::BLACK);
+        draw_polygon(backend, &frame);
+
+        let text = seat_label(p, computer);
+        let (tw, th) = backend.text_size(&text);
+        backend.render_text(&text, x + (w - tw as i32) / 2, y + (h - th as i32) / 2,
+                            Color(0x22, 0x22, 0x22));
+    }
+
+    if let Some(moves) = promotion {
+        let rects = promotion_picker_rects(ww, wh);
+        let (lx, ly, _, rh) = rects[0];
+        let (rx, _, rw, _) = rects[rects.len() - 1];
+        let pad = rh / 4;
+        let frame = [(lx - pad, ly - pad), (lx - pad, ly + rh + pad),
+                     (rx + rw + pad, ly - pad), (rx + rw + pad, ly + rh + pad)];
+
+        backend.set_draw_color(Color::WHITE);
+        fill_quadrilateral(backend, &frame);
+        backend.set_draw_color(Color::BLACK);
+        draw_polygon(backend, &frame);
+
+        for (mov, (x, y, w, h)) in moves.iter().zip(rects) {
+            let texture = textures[mov.piece.player as usize][mov.promotion.unwrap() as usize];
+            backend.draw_image(texture, x, y, w, h);
+        }
+    }
+
+    backend.present();
+
+    // the computer thinks after the previous move has been drawn
+    if computer[board.current_player() as usize] && plays_here(board, net)
+        && board.outcome().is_none() {
+        let backend = &*backend;
+        if let Some(mov) = ai.choose_move(board, &|| Duration::from_millis(backend.ticks())) {
+            play(board, mov, redo, net);
         }
     }
+    true
+}
+
+fn player_color(player: Player) -> Color {
+    match player {
+        Player::Red => Color::RED,
+        Player::Green => Color::GREEN,
+        Player::Yellow => Color(0xff, 0xbf, 0x00),
+    }
+}
 
-    let string = format!("{}'s turn", board.current_player);
-    let (w, h) = backend.text_size(&string);
-    backend.render_text(&string, ww - w as i32 - 10, h as i32 + 5,
-                        match board.current_player {
-                            Player::Red => Color::RED,
-                            Player::Green => Color::GREEN,
-                            Player::Yellow => Color(0xff, 0xbf, 0x00),
-                        });
+/// Writes whose turn it is, the clocks if there are any, who is in check or
+/// out of the game, and the final ranking once it is over.
+fn draw_status<B: backend::Backend>(backend: &mut B, board: &Board,
+                                    clocks: Option<(&Clocks, Duration)>, ww: i32) {
+    let (_, h) = backend.text_size("X");
+    let line = h as i32 + 5;
+
+    if board.outcome().is_none() {
+        let string = format!("{}'s turn", board.current_player());
+        let (w, _) = backend.text_size(&string);
+        backend.render_text(&string, ww - w as i32 - 10, line,
+                            player_color(board.current_player()));
+    }
+
+    if let Some((clocks, now)) = clocks {
+        for (i, p) in Player::ALL.into_iter().enumerate() {
+            let string = format!("{p} {}", format_time(clocks.left(p, now)));
+            let (w, _) = backend.text_size(&string);
+            backend.render_text(&string, ww - w as i32 - 10, line * (i as i32 + 2),
+                                player_color(p));
+        }
+    }
 
     let mut yind = 1;
-    for player in [Player::Red, Player::Green, Player::Yellow] {
-        if ! board.is_check(player) {
+    for player in Player::ALL {
+        if board.is_out(player) || ! board.is_check(player) {
             continue;
         }
         let string = format!("{player} is in check");
-        backend.render_text(&string, 10, (h as i32 + 5) * yind,
-                            match board.current_player {
-                                Player::Red => Color::RED,
-                                Player::Green => Color::GREEN,
-                                Player::Yellow => Color(0xff, 0xbf, 0x00),
-                            });
+        backend.render_text(&string, 10, line * yind, player_color(player));
         yind += 1;
     }
 
-    let mut mate_count = 0;
-    for (p, b) in mate.iter() {
-        if !*b {
-            continue;
-        }
-        let string = format!("{p} is mate");
-        backend.render_text(&string, 10, (h as i32 + 5) * yind,
-                            match board.current_player {
-                                Player::Red => Color::RED,
-                                Player::Green => Color::GREEN,
-                                Player::Yellow => Color(0xff, 0xbf, 0x00),
-                            });
+    for (p, status) in board.departures() {
+        let string = format!("{p} {status}");
+        backend.render_text(&string, 10, line * yind, player_color(p));
         yind += 1;
-        mate_count += 1;
     }
 
-    if mate_count == 2 {
-        let player = mate.iter().find(|(_p, a)| !*a)
-                                .unwrap().0;
-
-        let string = format!("{player} has won");
-        backend.render_text(&string, 10, (h as i32 + 5) * yind,
-                            match board.current_player {
-                                Player::Red => Color::RED,
-                                Player::Green => Color::GREEN,
-                                Player::Yellow => Color(0xff, 0xbf, 0x00),
-                            });
+    let Some(outcome) = board.outcome() else { return };
+    let color = match outcome {
+        GameOutcome::Won(player) => player_color(player),
+        _ => Color(0x22, 0x22, 0x22),
+    };
+    backend.render_text(&outcome.to_string(), 10, line * yind, color);
+    yind += 1;
+
+    for (place, p) in board.ranking() {
+        let string = format!("{place}. {p}");
+        backend.render_text(&string, 10, line * yind, player_color(p));
+        yind += 1;
     }
+}
 
-    for s in &board.sections {
+fn draw_fields<B: backend::Backend>(backend: &mut B, board: &Board, ww: i32, wh: i32) {
+    for s in board.sections() {
         for y in 0 .. 4 {
             for x in 0 .. 4 {
                 let points = s.get_coords(x, y, ww, wh);
@@ -1461,34 +458,13 @@ pub extern "C" fn main_loop_step() -> bool {
             }
         }
     }
+}
 
-    if let Some(f) = board.active_field {
-        let active_fields = f.get_possible_moves(&board);
-        let coords: Vec<_> = active_fields.iter().flat_map(|f| board.get_coords(*f, ww, wh)).collect();
-
-        for points in coords {
-            backend.set_draw_color(
-                [Color::RED, Color::GREEN, Color::YELLOW][board.current_player as usize]
-            );
-            let mx = (points[0].0 + points[1].0 + points[2].0 + points[3].0) / 4;
-            let my = (points[0].1 + points[1].1 + points[2].1 + points[3].1) / 4;
-
-            let r = ww.min(wh) / 40;
-
-            for rx in (mx-r) ..= (mx+r) {
-                for ry in (my-r) ..= (my+r) {
-                    let x = rx - mx;
-                    let y = ry - my;
-
-                    if x * x + y * y < r * r {
-                        backend.draw_point(rx, ry);
-                    }
-                }
-            }
-        }
-    }
-
-    for s in board.sections {
+/// Draws the pieces on their fields, leaving out the one on `hidden`, and
+/// neutral ones on a grey disc.
+fn draw_pieces<B: backend::Backend>(backend: &mut B, board: &Board, textures: &[Vec<ImageHandle>],
+                                    hidden: Option<Coord>, ww: i32, wh: i32) {
+    for s in board.sections() {
         for y in 0 .. 4 {
             for x in 0 .. 4 {
                 let points = s.get_coords(x, y, ww, wh);
@@ -1497,51 +473,348 @@ pub extern "C" fn main_loop_step() -> bool {
                 let my = (points[0].1 + points[1].1 + points[2].1 + points[3].1) / 4;
 
                 if let Some(p) = f.piece {
+                    if hidden == Some(f.coord) {
+                        continue;
+                    }
                     let color = p.player as usize;
                     let piece = p.typ as usize;
 
-                    let texture = &textures[color][piece];
+                    let texture = textures[color][piece];
 
                     let (_, height) = s.get_radius_and_height(ww, wh);
 
                     let w = height / 6;
                     let h = w;
 
-                    backend.render_png_data(&texture.data,
-                                            mx - w / 2, my - h / 2, w, h);
+                    // pieces left behind by players who are out are neutral
+                    if board.is_out(p.player) {
+                        backend.set_draw_color(Color(0x99, 0x99, 0x99));
+                        backend.fill_circle((mx, my), w / 2);
+                    }
+                    backend.draw_image(texture, mx - w / 2, my - h / 2, w, h);
                 }
             }
         }
     }
-    backend.present();
+}
+
+/// Some number that differs from run to run.
+#[cfg(not(target_family = "wasm"))]
+fn seed() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+#[cfg(target_family = "wasm")]
+fn seed() -> u64 {
+    wasm_backend::seed()
+}
+
+const RECORD_PATH: &str = "./last_game.pgn";
+
+/// Today's date in the `YYYY.MM.DD` form of game records.
+#[cfg(not(target_family = "wasm"))]
+fn record_date() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    // civil date from days since 1970-01-01, after Howard Hinnant
+    let z = secs / 86400 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// The browser build has no clock to ask, so the date is left unknown.
+#[cfg(target_family = "wasm")]
+fn record_date() -> String {
+    "????.??.??".to_string()
+}
+
+fn save_record(backend: &mut BackendType, board: &Board) {
+    let mut record = GameRecord::default();
+    record.set_tag("Event", "threechess game");
+    record.set_tag("Date", &record_date());
+    for tag in ["Red", "Green", "Yellow"] {
+        record.set_tag(tag, "?");
+    }
+
+    let moves = GameRecord::from_board(board);
+    for (name, value) in &moves.tags {
+        record.set_tag(name, value);
+    }
+    record.moves = moves.moves;
+
+    backend.write_file(RECORD_PATH, record.to_string().as_bytes());
+    println!("game record written to {RECORD_PATH}");
+}
+
+const BUTTONS: [&str; 2] = ["undo", "redo"];
+
+/// How far in pixels the cursor has to travel before a pressed piece
+/// follows it.
+const DRAG_THRESHOLD: i32 = 4;
+
+/// A piece held with the mouse button down.
+struct Drag {
+    from: Coord,
+    start: (i32, i32),
+    pos: (i32, i32),
+    moved: bool,
+}
+
+fn field_at(board: &Board, x: i32, y: i32, ww: i32, wh: i32) -> Option<Coord> {
+    for s in board.sections() {
+        for yi in 0..4 {
+            for xi in 0..4 {
+                let coords = s.get_coords(xi, yi, ww, wh);
+                if point_is_in_quadrilateral((x, y), &coords) {
+                    return Some(s.fields[xi][yi].coord);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Plays the move from `from` to `to` if there is one, asking for the piece
+/// first when a pawn promotes.
+fn try_move(board: &mut Board, from: Coord, to: Coord, promotion: &mut Option<Vec<Move>>,
+            redo: &mut Vec<Move>, net: &mut Option<Net>) {
+    let moves = board.find_moves(from, to);
+    if moves.len() == 1 {
+        play(board, moves[0], redo, net);
+    } else if ! moves.is_empty() {
+        *promotion = Some(moves);
+    }
+}
+
+/// Plays `mov`, or sends it to the server in a network game. The board then
+/// only changes once the server passes the move back.
+fn play(board: &mut Board, mov: Move, redo: &mut Vec<Move>, net: &mut Option<Net>) {
+    match net {
+        Some(n) => {
+            if let Err(e) = n.client.send(&ClientMessage::Move(board.format_move(&mov))) {
+                eprintln!("could not send move: {e}");
+            }
+            n.waiting = true;
+        },
+        None => {
+            board.apply_move(mov).unwrap();
+            redo.clear();
+        },
+    }
+}
+
+/// The connection to a `threechess-server` in a network game.
+struct Net {
+    client: ConnectionType,
+    address: String,
+    /// The seat the server gave us; without one we only watch.
+    seat: Option<Player>,
+    /// Whether a move was sent and the server has not answered yet.
+    waiting: bool,
+}
+
+/// Whether the player to move is played at this screen, which in a network
+/// game is only our own seat.
+fn plays_here(board: &Board, net: &Option<Net>) -> bool {
+    net.as_ref().is_none_or(|n| n.seat == Some(board.current_player()) && ! n.waiting)
+}
+
+fn human_turn(board: &Board, computer: &[bool; 3], net: &Option<Net>) -> bool {
+    plays_here(board, net) && ! computer[board.current_player() as usize]
+}
+
+/// Follows what the server sent since the last frame, including its clocks.
+/// Returns false once the connection is lost.
+fn receive(board: &mut Board, net: &mut Net, clocks: &mut Option<Clocks>, now: Duration) -> bool {
+    let messages = match net.client.poll() {
+        Ok(messages) => messages,
+        Err(e) => {
+            eprintln!("lost the connection to {}: {e}", net.address);
+            return false;
+        },
+    };
+    for message in messages {
+        match message {
+            ServerMessage::Rules(rules) => board.set_rules(rules),
+            ServerMessage::Clock(control) => *clocks = Some(Clocks::new(control)),
+            ServerMessage::Joined(seat) => net.seat = Some(seat),
+            // playing the move keeps it in the history; if that fails the
+            // position following it sets the board right
+            ServerMessage::Moved(_, text) => if let Ok(mov) = board.parse_move(&text) {
+                board.apply_move(mov).unwrap();
+            },
+            ServerMessage::Position(position) => {
+                net.waiting = false;
+                if board.to_notation() != position {
+                    match Board::from_notation(&position) {
+                        Ok(mut b) => {
+                            b.set_rules(board.rules());
+                            *board = b;
+                        },
+                        Err(e) => { eprintln!("invalid position from the server: {e}"); },
+                    }
+                }
+            },
+            ServerMessage::Time(left, running) => if let Some(c) = clocks {
+                c.set(left, running, now);
+            },
+            ServerMessage::Flagged(seat) => {
+                println!("{seat} ran out of time");
+                if ! board.is_out(seat) {
+                    board.eliminate(seat, PlayerStatus::TimedOut);
+                }
+            },
+            ServerMessage::Error(e) => {
+                net.waiting = false;
+                eprintln!("{e}");
+            },
+        }
+    }
     true
 }
 
-#[derive(Debug)]
-pub struct Image {
-    pub data: Vec<u8>,
+/// Keeps the clock of the player to move running, and drops players whose
+/// time is up out of the game.
+fn run_clocks(board: &mut Board, clocks: &mut Clocks, now: Duration) {
+    let player = board.current_player();
+    if clocks.running() == Some(player) && clocks.flagged(player, now) {
+        println!("{player} ran out of time");
+        board.eliminate(player, PlayerStatus::TimedOut);
+    }
+
+    if board.outcome().is_some() {
+        clocks.stop(now);
+    } else if clocks.running() != Some(board.current_player()) {
+        clocks.start(board.current_player(), now);
+        clocks.mark(board.history().count());
+    }
+}
+
+/// Takes back moves up to the last one of a human player, as the computer
+/// would answer straight away again otherwise. The clocks go back to the
+/// times left when that player's turn began.
+fn undo(board: &mut Board, redo: &mut Vec<Move>, record_saved: &mut bool, computer: &[bool; 3],
+        clocks: &mut Option<Clocks>) {
+    let taken_back = redo.len();
+    while let Some(mov) = board.undo_move() {
+        redo.push(mov);
+        *record_saved = false;
+        if ! computer[board.current_player() as usize] {
+            break;
+        }
+    }
+    if let Some(c) = clocks.as_mut().filter(|_| redo.len() > taken_back) {
+        c.rewind(board.history().count());
+    }
+}
+
+fn redo_move(board: &mut Board, redo: &mut Vec<Move>, computer: &[bool; 3],
+             clocks: &mut Option<Clocks>) {
+    let to_redo = redo.len();
+    while let Some(mov) = redo.pop() {
+        board.apply_move(mov).unwrap();
+        if ! computer[board.current_player() as usize] {
+            break;
+        }
+    }
+    if let Some(c) = clocks.as_mut().filter(|_| redo.len() < to_redo) {
+        c.rewind(board.history().count());
+    }
+}
+
+fn seat_label(player: Player, computer: &[bool; 3]) -> String {
+    let who = if computer[player as usize] { "computer" } else { "human" };
+    format!("{player}: {who}")
+}
+
+/// The buttons in the lower right corner switching each seat between a
+/// human and the computer, in the order of `Player::ALL`.
+fn seat_button_rects(backend: &BackendType, computer: &[bool; 3],
+                     ww: i32, wh: i32) -> Vec<(i32, i32, i32, i32)> {
+    let mut y = wh - 10;
+    Player::ALL.iter().rev().map(|p| {
+        let (tw, th) = backend.text_size(&seat_label(*p, computer));
+        let (w, h) = (tw as i32 + 16, th as i32 + 10);
+        y -= h;
+        let rect = (ww - w - 10, y, w, h);
+        y -= 10;
+        rect
+    }).collect::<Vec<_>>().into_iter().rev().collect()
+}
+
+/// The on-screen buttons in the lower left corner, in the order of `BUTTONS`.
+fn button_rects(backend: &BackendType, wh: i32) -> Vec<(i32, i32, i32, i32)> {
+    let mut x = 10;
+    BUTTONS.iter().map(|text| {
+        let (tw, th) = backend.text_size(text);
+        let (w, h) = (tw as i32 + 16, th as i32 + 10);
+        let rect = (x, wh - h - 10, w, h);
+        x += w + 10;
+        rect
+    }).collect()
 }
 
-fn load_textures(backend: &BackendType) -> std::io::Result<Vec<Vec<Image>>> {
+fn rect_contains((rx, ry, rw, rh): (i32, i32, i32, i32), x: i32, y: i32) -> bool {
+    x >= rx && x < rx + rw && y >= ry && y < ry + rh
+}
+
+/// Where the choices of the promotion picker are drawn, in the order of
+/// `PieceType::PROMOTIONS`.
+fn promotion_picker_rects(ww: i32, wh: i32) -> [(i32, i32, i32, i32); 4] {
+    let size = ww.min(wh) / 10;
+    let x0 = ww / 2 - 2 * size;
+    let y0 = wh / 2 - size / 2;
+
+    [0, 1, 2, 3].map(|i| (x0 + i * size, y0, size, size))
+}
+
+fn load_textures<B: backend::Backend>(backend: &mut B) -> std::io::Result<Vec<Vec<ImageHandle>>> {
     let mut vec = vec![];
     for c in COLOR_LETTERS {
         let mut inner_vec = vec![];
         for p in PIECE_LETTERS {
             let data = backend.read_file(&format!("./assets/{p}{c}.png"));
 
-            inner_vec.push(Image {
-                data,
-            });
+            inner_vec.push(backend.load_image(&data));
         }
         vec.push(inner_vec);
     }
     Ok(vec)
 }
 
-static mut __BOARD: Option<Board> = None;
-static mut __TEXTURES: Option<Vec<Vec<Image>>> = None;
-static mut __BACKEND: Option<BackendType> = None;
-static mut __MATE: Option<HashMap<Player, bool>> = None;
+struct State {
+    board: Board,
+    textures: Vec<Vec<ImageHandle>>,
+    backend: BackendType,
+    active_field: Option<Coord>,
+    /// The choices offered while a promoting pawn waits for its new piece.
+    promotion: Option<Vec<Move>>,
+    record_saved: bool,
+    /// Moves taken back with undo, the most recent last.
+    redo: Vec<Move>,
+    drag: Option<Drag>,
+    /// The seats played by the computer, indexed by `Player`.
+    computer: [bool; 3],
+    ai: Ai,
+    net: Option<Net>,
+    clocks: Option<Clocks>,
+}
+
+static mut __STATE: Option<State> = None;
+
+fn state() -> &'static mut State {
+    let state = &raw mut __STATE;
+    unsafe { (*state).as_mut().unwrap() }
+}
 
 #[export_name = "init"]
 pub extern "C" fn init() {
@@ -1549,21 +822,175 @@ pub extern "C" fn init() {
         eprintln!("{i}");
     }));
 
-    unsafe{__BOARD = Some(Board::new())};
-    unsafe{__BOARD.as_mut().unwrap()}.place_pieces();
+    let options = parse_args();
+    let mut board = options.rules.variant.setup();
+    board.set_rules(options.rules);
+
+    let net = options.connect.map(|address| {
+        let mut client = ConnectionType::connect(&address).unwrap_or_else(|e| {
+            eprintln!("could not connect to {address}: {e}");
+            std::process::exit(1);
+        });
+        if let Some(seat) = options.seat {
+            client.send(&ClientMessage::Join(seat)).unwrap();
+        }
+        Net { client, address, seat: None, waiting: false }
+    });
+
+    let mut clocks = options.clock.map(Clocks::new);
+    if clocks.is_some() && net.is_some() {
+        eprintln!("the server keeps the clocks in a network game, see its --clock");
+        clocks = None;
+    }
+
+    let mut backend = BackendType::new();
+    let textures = load_textures(&mut backend).unwrap();
+
+    unsafe {__STATE = Some(State {
+        board,
+        textures,
+        backend,
+        active_field: None,
+        promotion: None,
+        record_saved: false,
+        redo: vec![],
+        drag: None,
+        computer: options.computer,
+        ai: options.ai,
+        net,
+        clocks,
+    })};
+}
+
+struct Options {
+    computer: [bool; 3],
+    ai: Ai,
+    /// The server to play on instead of this screen alone.
+    connect: Option<String>,
+    seat: Option<Player>,
+    clock: Option<TimeControl>,
+    rules: Rules,
+}
+
+fn parse_player(name: &str) -> Option<Player> {
+    let player = Player::ALL.into_iter().find(|p| p.to_string() == name);
+    if player.is_none() {
+        eprintln!("unknown player '{name}'");
+    }
+    player
+}
+
+/// Reads `--computer red,yellow`, `--depth <plies>`, `--time <ms>`,
+/// `--connect <address>`, `--seat <player>`, `--clock <time control>`,
+/// `--variant <variant>`, `--mate-credit mover|checker` and
+/// `--eliminated neutral|removed|captured` from the command line, or in the
+/// browser from the page's query, like `?connect=localhost:7532`.
+fn parse_args() -> Options {
+    let mut options = Options {
+        computer: [false; 3],
+        ai: Ai::default(),
+        connect: None,
+        seat: None,
+        clock: None,
+        rules: Rules::default(),
+    };
+
+    #[cfg(not(target_family = "wasm"))]
+    let mut args = std::env::args().skip(1);
+    #[cfg(target_family = "wasm")]
+    let mut args = wasm_backend::args().into_iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_default();
+        match arg.as_str() {
+            "--computer" => for name in value.split(',') {
+                if let Some(p) = parse_player(name) {
+                    options.computer[p as usize] = true;
+                }
+            },
+            "--depth" => match value.parse() {
+                Ok(d) => options.ai.depth = d,
+                Err(_) => { eprintln!("invalid depth '{value}'"); },
+            },
+            "--time" => match value.parse() {
+                Ok(ms) => options.ai.time_budget = Some(Duration::from_millis(ms)),
+                Err(_) => { eprintln!("invalid time '{value}'"); },
+            },
+            "--connect" => options.connect = Some(value),
+            "--seat" => options.seat = parse_player(&value),
+            "--clock" => match value.parse() {
+                Ok(control) => options.clock = Some(control),
+                Err(e) => { eprintln!("{e}"); },
+            },
+            "--variant" => match Variant::parse_or_random(&value, seed()) {
+                Ok(variant) => options.rules.variant = variant,
+                Err(e) => { eprintln!("{e}"); },
+            },
+            "--mate-credit" => match value.parse() {
+                Ok(credit) => options.rules.mate_credit = credit,
+                Err(e) => { eprintln!("{e}"); },
+            },
+            "--eliminated" => match value.parse() {
+                Ok(pieces) => options.rules.eliminated_pieces = pieces,
+                Err(e) => { eprintln!("{e}"); },
+            },
+            _ => { eprintln!("unknown argument '{arg}'"); },
+        }
+    }
+    options
+}
+
+/// `threechess render [--size <pixels>] <position> <file.png>` draws a
+/// position, given in position notation or as a file holding either that or
+/// a game record, into a PNG file.
+#[cfg(not(target_family = "wasm"))]
+fn render(args: &[String]) -> Result<(), String> {
+    let mut size = 800;
+    let mut args = args.iter();
+    let mut paths = vec![];
+    while let Some(arg) = args.next() {
+        if arg == "--size" {
+            let value = args.next().ok_or("missing size")?;
+            size = value.parse().map_err(|_| format!("invalid size '{value}'"))?;
+        } else {
+            paths.push(arg);
+        }
+    }
+    let [position, out] = paths[..] else {
+        return Err("usage: threechess render [--size <pixels>] <position> <file.png>".to_string());
+    };
+
+    let text = std::fs::read_to_string(position).unwrap_or_else(|_| position.clone());
+    let board = Board::from_notation(text.trim())
+        .or_else(|_| GameRecord::parse(&text).and_then(|r| r.replay()))
+        .map_err(|e| e.to_string())?;
 
-    unsafe {__BACKEND = Some(BackendType::new())};
-    unsafe {__TEXTURES = Some(load_textures(__BACKEND.as_ref().unwrap()).unwrap())};
+    let mut backend = image_backend::ImageBackend::new(size, size);
+    let textures = load_textures(&mut backend).map_err(|e| e.to_string())?;
+    let (ww, wh) = (size as i32, size as i32);
 
-    unsafe{__MATE = Some(HashMap::new())};
-    let mate = unsafe{__MATE.as_mut().unwrap()};
-    mate.insert(Player::Green, false);
-    mate.insert(Player::Red, false);
-    mate.insert(Player::Yellow, false);
+    backend.set_draw_color(Color::WHITE);
+    backend.clear();
+    draw_status(&mut backend, &board, None, ww);
+    draw_fields(&mut backend, &board, ww, wh);
+    draw_pieces(&mut backend, &board, &textures, None, ww, wh);
 
+    std::fs::write(out, backend.encode_png()).map_err(|e| format!("could not write {out}: {e}"))
 }
 
 fn main() {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let args: Vec<String> = std::env::args().collect();
+        if args.get(1).is_some_and(|a| a == "render") {
+            if let Err(e) = render(&args[2 ..]) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+    }
+
     init();
     while main_loop_step() {};
 }
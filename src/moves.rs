@@ -0,0 +1,41 @@
+use crate::board::Coord;
+use crate::castling::CastlingSide;
+use crate::piece::{Piece, PieceType};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MoveKind {
+    #[default] Normal,
+    /// The king's part of a castling; the rook follows along.
+    Castling(CastlingSide),
+    /// A pawn capture onto a skipped field; holds the field of the pawn
+    /// that is taken.
+    EnPassant(Coord),
+}
+
+/// A single move of one piece, with everything needed to take it back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub from: Coord,
+    pub to: Coord,
+    pub piece: Piece,
+    pub captured: Option<Piece>,
+    pub promotion: Option<PieceType>,
+    pub kind: MoveKind,
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}-{}", self.from, self.to)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IllegalMove(pub Move);
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "illegal move {}", self.0)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
@@ -0,0 +1,519 @@
+//! Text formats for positions and moves.
+//!
+//! A position is written as five or six space separated parts:
+//!
+//! ```text
+//! rrnrbrqrkrbrnrrr/prprprprprprprpr/8/8/8/8/pgpgpgpgpgpgpgpg/rgngbgkgqgbgngrg/8/8/pypypypypypypypy/qybynyrykybynyry r - rKQgKQyKQ -
+//! ```
+//!
+//! 1. The 96 fields, rank 1 first and each rank in file order (`a`-`h` on
+//!    ranks 1-4, `a`-`d` and `i`-`l` on ranks 5-8, `e`-`l` on ranks 9-12).
+//!    A piece is its letter from `PIECE_LETTERS` followed by its colour from
+//!    `COLOR_LETTERS`, like the texture names; a digit skips empty fields.
+//! 2. The player to move.
+//! 3. The players that are out or drawn, in the order they left, or `-`.
+//!    Each colour is followed by how it left: `M` and the colour credited
+//!    with the mate, `S` when stalemated, `R` resigned, `T` out of time,
+//!    `D` drawn. A colour on its own is checkmated without credit.
+//! 4. Castling rights, each player's colour followed by `K` and/or `Q`, or `-`.
+//! 5. Fields skipped by double steps and still open for en passant, each
+//!    prefixed with the colour of the pawn's owner and separated by commas,
+//!    or `-`.
+//! 6. The moves played since the last capture or pawn move, left out when
+//!    there are none.
+//!
+//! Moves use standard algebraic notation on the board's file and rank labels:
+//! `e4`, `Nk6`, `Bxj10`, `dxe5`, `O-O`, `O-O-O`, `h12=N`. Fields are written
+//! in lower case so that a file like `b` never reads as a bishop. A `+`
+//! follows a move that checks any opponent and a `#` one that mates.
+//! `Board::parse_move` also accepts coordinates like `e2e4` or `e7-e8=Q`.
+
+use crate::board::{Board, Coord, EnPassant};
+use crate::castling::CastlingSide;
+use crate::moves::{Move, MoveKind};
+use crate::piece::{Piece, PieceType, Player};
+use crate::rules::PlayerStatus;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError(pub String);
+
+impl std::fmt::Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err(NotationError(::std::format!($($arg)*)))
+    };
+}
+
+impl std::str::FromStr for Coord {
+    type Err = NotationError;
+
+    /// Parses a field name like `e4` or `E4`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let file = match chars.next() {
+            Some(c) => c.to_ascii_lowercase(),
+            None => bail!("empty field name"),
+        };
+
+        let coord = match chars.as_str().parse() {
+            Ok(rank) => Coord(file, rank),
+            Err(_) => bail!("invalid field name '{s}'"),
+        };
+
+        if ! coord.is_valid() {
+            bail!("there is no field '{s}'");
+        }
+        Ok(coord)
+    }
+}
+
+impl Board {
+    pub fn to_notation(&self) -> String {
+        let mut ranks = vec![];
+        for rank in 1 ..= 12 {
+            let mut s = String::new();
+            let mut empty = 0;
+            for file in Coord::files_of_rank(rank) {
+                match self.get_field(*file, rank).unwrap().piece {
+                    Some(p) => {
+                        if empty > 0 {
+                            s.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        s.push(p.typ.letter());
+                        s.push(p.player.letter());
+                    },
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                s.push_str(&empty.to_string());
+            }
+            ranks.push(s);
+        }
+
+        let or_dash = |s: String| if s.is_empty() { "-".to_string() } else { s };
+
+        let mut out = String::new();
+        for (player, status) in self.departures() {
+            out.push(player.letter());
+            match status {
+                PlayerStatus::Playing | PlayerStatus::Checkmated { by: None } => (),
+                PlayerStatus::Checkmated { by: Some(by) } => {
+                    out.push('M');
+                    out.push(by.letter());
+                },
+                PlayerStatus::Stalemated => out.push('S'),
+                PlayerStatus::Resigned => out.push('R'),
+                PlayerStatus::TimedOut => out.push('T'),
+                PlayerStatus::Drawn => out.push('D'),
+            }
+        }
+
+        let mut castling = String::new();
+        for player in Player::ALL {
+            let king = self.has_castling_right(player, CastlingSide::King);
+            let queen = self.has_castling_right(player, CastlingSide::Queen);
+            if king || queen {
+                castling.push(player.letter());
+            }
+            if king {
+                castling.push('K');
+            }
+            if queen {
+                castling.push('Q');
+            }
+        }
+
+        let en_passant = Player::ALL.into_iter()
+            .filter_map(|p| self.en_passant(p)
+                        .map(|e| format!("{}{}", p.letter(), e.target.to_string().to_lowercase())))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut s = format!("{} {} {} {} {}", ranks.join("/"), self.current_player().letter(),
+                            or_dash(out), or_dash(castling), or_dash(en_passant));
+        if self.halfmoves() > 0 {
+            s.push_str(&format!(" {}", self.halfmoves()));
+        }
+        s
+    }
+
+    pub fn from_notation(s: &str) -> Result<Self, NotationError> {
+        let parts: Vec<_> = s.split_whitespace().collect();
+        if parts.len() != 5 && parts.len() != 6 {
+            bail!("expected 5 or 6 parts, found {}", parts.len());
+        }
+
+        let mut board = Board::new();
+
+        let ranks: Vec<_> = parts[0].split('/').collect();
+        if ranks.len() != 12 {
+            bail!("expected 12 ranks, found {}", ranks.len());
+        }
+
+        for (i, s) in ranks.into_iter().enumerate() {
+            let rank = i + 1;
+            let files = Coord::files_of_rank(rank);
+            let mut idx = 0;
+            let mut chars = s.chars();
+            while let Some(c) = chars.next() {
+                if let Some(n) = c.to_digit(10) {
+                    idx += n as usize;
+                    continue;
+                }
+
+                let typ = PieceType::from_letter(c);
+                let player = chars.next().and_then(Player::from_letter);
+                let (typ, player) = match (typ, player) {
+                    (Some(t), Some(p)) => (t, p),
+                    _ => bail!("invalid piece in rank {rank}: '{s}'"),
+                };
+
+                if idx >= files.len() {
+                    bail!("too many fields in rank {rank}: '{s}'");
+                }
+                board.get_field_mut(files[idx], rank).unwrap().piece = Some(Piece {
+                    typ,
+                    player,
+                });
+                idx += 1;
+            }
+
+            if idx != files.len() {
+                bail!("rank {rank} has {idx} fields instead of {}: '{s}'", files.len());
+            }
+        }
+
+        let mut side = parts[1].chars();
+        match (side.next().and_then(Player::from_letter), side.next()) {
+            (Some(p), None) => board.set_current_player(p),
+            _ => bail!("invalid player to move '{}'", parts[1]),
+        }
+
+        if parts[2] != "-" {
+            let mut chars = parts[2].chars().peekable();
+            while let Some(c) = chars.next() {
+                let player = match Player::from_letter(c) {
+                    Some(p) if board.status(p) == PlayerStatus::Playing => p,
+                    _ => bail!("invalid player out of the game '{c}'"),
+                };
+                let status = match chars.peek() {
+                    Some('M') => {
+                        chars.next();
+                        match chars.next().and_then(Player::from_letter) {
+                            Some(by) => PlayerStatus::Checkmated { by: Some(by) },
+                            None => bail!("missing mating player in '{}'", parts[2]),
+                        }
+                    },
+                    Some('S') => PlayerStatus::Stalemated,
+                    Some('R') => PlayerStatus::Resigned,
+                    Some('T') => PlayerStatus::TimedOut,
+                    Some('D') => PlayerStatus::Drawn,
+                    _ => PlayerStatus::Checkmated { by: None },
+                };
+                if ! matches!(status, PlayerStatus::Checkmated { .. }) {
+                    chars.next();
+                }
+                board.set_status(player, status);
+            }
+        }
+
+        for player in Player::ALL {
+            board.set_castling_right(player, CastlingSide::King, false);
+            board.set_castling_right(player, CastlingSide::Queen, false);
+        }
+        if parts[3] != "-" {
+            let mut player = None;
+            for c in parts[3].chars() {
+                match (c, player) {
+                    ('K', Some(p)) => board.set_castling_right(p, CastlingSide::King, true),
+                    ('Q', Some(p)) => board.set_castling_right(p, CastlingSide::Queen, true),
+                    _ => match Player::from_letter(c) {
+                        Some(p) => player = Some(p),
+                        None => bail!("invalid castling rights '{}'", parts[3]),
+                    },
+                }
+            }
+        }
+
+        if parts[4] != "-" {
+            for e in parts[4].split(',') {
+                let mut chars = e.chars();
+                let player = match chars.next().and_then(Player::from_letter) {
+                    Some(p) => p,
+                    None => bail!("invalid en passant field '{e}'"),
+                };
+                let target: Coord = chars.as_str().parse()?;
+                let pawn = match board.pawn_step(player, target) {
+                    Some(c) => c,
+                    None => bail!("no pawn can have skipped '{e}'"),
+                };
+
+                board.set_en_passant(player, Some(EnPassant {
+                    target,
+                    pawn,
+                }));
+            }
+        }
+
+        if let Some(halfmoves) = parts.get(5) {
+            match halfmoves.parse() {
+                Ok(n) => board.set_halfmoves(n),
+                Err(_) => bail!("invalid move count '{halfmoves}'"),
+            }
+        }
+
+        Ok(board)
+    }
+
+    /// Writes `mov`, a legal move in this position, in algebraic notation.
+    pub fn format_move(&self, mov: &Move) -> String {
+        let moves = self.legal_moves(mov.piece.player);
+        let mut s = self.format_move_plain(mov, &moves);
+
+        let mut after = self.clone();
+        after.apply_move_unchecked(*mov);
+        let mated = |b: &Board, p| matches!(b.status(p), PlayerStatus::Checkmated { .. });
+        if Player::ALL.into_iter().any(|p| mated(&after, p) && ! mated(self, p)) {
+            s.push('#');
+        } else if Player::ALL.into_iter().any(|p| p != mov.piece.player && ! after.is_out(p)
+                                              && after.is_check(p)) {
+            s.push('+');
+        }
+        s
+    }
+
+    /// Algebraic notation of `mov` without check or mate suffix; `moves` are
+    /// all legal moves of the moving player, used for disambiguation.
+    fn format_move_plain(&self, mov: &Move, moves: &[Move]) -> String {
+        match mov.kind {
+            MoveKind::Castling(CastlingSide::King) => return "O-O".to_string(),
+            MoveKind::Castling(CastlingSide::Queen) => return "O-O-O".to_string(),
+            _ => (),
+        }
+
+        let field = |c: Coord| format!("{}{}", c.0, c.1);
+        let capture = mov.captured.is_some();
+
+        let others: Vec<_> = moves.iter()
+            .filter(|m| m.to == mov.to && m.from != mov.from
+                    && m.piece.typ == mov.piece.typ)
+            .collect();
+        let file_unique = others.iter().all(|m| m.from.0 != mov.from.0);
+        let rank_unique = others.iter().all(|m| m.from.1 != mov.from.1);
+
+        let mut s = String::new();
+        if mov.piece.typ == PieceType::Pawn {
+            if capture {
+                if file_unique {
+                    s.push(mov.from.0);
+                } else {
+                    s.push_str(&field(mov.from));
+                }
+            }
+        } else {
+            s.push(mov.piece.typ.letter().to_ascii_uppercase());
+            if ! others.is_empty() {
+                if file_unique {
+                    s.push(mov.from.0);
+                } else if rank_unique {
+                    s.push_str(&mov.from.1.to_string());
+                } else {
+                    s.push_str(&field(mov.from));
+                }
+            }
+        }
+
+        if capture {
+            s.push('x');
+        }
+        s.push_str(&field(mov.to));
+
+        if let Some(p) = mov.promotion {
+            s.push('=');
+            s.push(p.letter().to_ascii_uppercase());
+        }
+        s
+    }
+
+    /// Reads a move of the current player, written either in algebraic
+    /// notation or as a pair of fields.
+    pub fn parse_move(&self, s: &str) -> Result<Move, NotationError> {
+        let plain = s.trim().trim_end_matches(['+', '#', '!', '?']);
+        let moves = self.legal_moves(self.current_player());
+
+        let plain = match plain {
+            "0-0" => "O-O",
+            "0-0-0" => "O-O-O",
+            x => x,
+        };
+
+        if let Some(m) = moves.iter().find(|m| self.format_move_plain(m, &moves) == plain) {
+            return Ok(*m);
+        }
+
+        if let Some((from, to, promotion)) = parse_coordinate_move(plain) {
+            if let Some(m) = moves.iter().find(|m| m.from == from && m.to == to
+                                               && m.promotion == promotion) {
+                return Ok(*m);
+            }
+        }
+
+        bail!("'{s}' is not a legal move for {}", self.current_player())
+    }
+}
+
+/// Splits a move like `e2e4`, `e2-e4`, `e7e8q` or `e7-e8=Q` into its parts.
+fn parse_coordinate_move(s: &str) -> Option<(Coord, Coord, Option<PieceType>)> {
+    let s = s.to_ascii_lowercase().replace(['-', '=', ' '], "");
+    let split = s.char_indices().skip(1)
+        .find(|(_, c)| c.is_ascii_alphabetic())?.0;
+    let (from, rest) = s.split_at(split);
+    let end = rest.char_indices().skip(1)
+        .find(|(_, c)| c.is_ascii_alphabetic())
+        .map_or(rest.len(), |(i, _)| i);
+    let (to, promotion) = rest.split_at(end);
+
+    let promotion = match promotion.len() {
+        0 => None,
+        1 => Some(PieceType::from_letter(promotion.chars().next()?)?),
+        _ => return None,
+    };
+
+    Some((from.parse().ok()?, to.parse().ok()?, promotion))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "rrnrbrqrkrbrnrrr/prprprprprprprpr/8/8/8/8/pgpgpgpgpgpgpgpg/rgngbgkgqgbgngrg\
+                         /8/8/pypypypypypypypy/qybynyrykybynyry r - rKQgKQyKQ -";
+
+    fn start() -> Board {
+        let mut board = Board::new();
+        board.place_pieces();
+        board
+    }
+
+    fn play(board: &mut Board, from: &str, to: &str) {
+        let mov = board.find_move(from.parse().unwrap(), to.parse().unwrap(), None).unwrap();
+        board.apply_move(mov).unwrap();
+    }
+
+    #[test]
+    fn starting_position() {
+        assert_eq!(start().to_notation(), START);
+        assert_eq!(Board::from_notation(START).unwrap().to_notation(), START);
+    }
+
+    #[test]
+    fn round_trip_after_moves() {
+        let mut board = start();
+        play(&mut board, "e2", "e4");
+        play(&mut board, "d7", "d5");
+        play(&mut board, "l11", "l9");
+        play(&mut board, "e1", "e2");
+
+        let notation = board.to_notation();
+        assert!(notation.ends_with(" g - gKQyKQ gd6,yl10 1"), "{notation}");
+
+        let parsed = Board::from_notation(&notation).unwrap();
+        assert_eq!(parsed.to_notation(), notation);
+        assert_eq!(parsed.legal_moves(Player::Green), board.legal_moves(Player::Green));
+        assert_eq!(parsed.en_passant(Player::Yellow), board.en_passant(Player::Yellow));
+    }
+
+    #[test]
+    fn mated_players() {
+        let notation = "8/8/8/4kr3/8/8/8/3kgqr3/8/8/8/4ky3 g y - -";
+        let board = Board::from_notation(notation).unwrap();
+        assert!(board.is_out(Player::Yellow));
+        assert!(! board.is_out(Player::Red));
+        assert_eq!(board.current_player(), Player::Green);
+        assert_eq!(board.to_notation(), notation);
+    }
+
+    #[test]
+    fn player_statuses() {
+        let notation = "8/8/8/4kr3/8/8/8/3kgqr3/8/8/8/4ky3 r yMrgR - -";
+        let board = Board::from_notation(notation).unwrap();
+        assert_eq!(board.status(Player::Yellow), PlayerStatus::Checkmated { by: Some(Player::Red) });
+        assert_eq!(board.status(Player::Green), PlayerStatus::Resigned);
+        assert_eq!(board.ranking(), [(1, Player::Red), (2, Player::Green), (3, Player::Yellow)]);
+        assert_eq!(board.to_notation(), notation);
+
+        for bad in ["yM", "yy", "yX", "bS"] {
+            assert!(Board::from_notation(&notation.replace("yMrgR", bad)).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn invalid_notation() {
+        assert!(Board::from_notation("").is_err());
+        assert!(Board::from_notation("8/8/8 r - - -").is_err());
+        assert!(Board::from_notation(&START.replace("qybynyrykybynyry", "qybynyrykybynyryp")).is_err());
+        assert!(Board::from_notation(&START.replace("/8/8/pypy", "/9/8/pypy")).is_err());
+        assert!(Board::from_notation(&START.replace("prprprprprprprpr", "xrprprprprprprpr")).is_err());
+        assert!(Board::from_notation(&START.replace(" r - ", " x - ")).is_err());
+        assert!(Board::from_notation(&START.replace(" -", " e3")).is_err());
+        assert!(Board::from_notation(&format!("{START} x")).is_err());
+    }
+
+    #[test]
+    fn move_notation() {
+        let mut board = start();
+        let e4 = board.parse_move("e4").unwrap();
+        assert_eq!(e4.from, Coord('e', 2));
+        assert_eq!(board.format_move(&e4), "e4");
+        assert_eq!(board.parse_move("e2-e4"), Ok(e4));
+        assert_eq!(board.parse_move("E2E4"), Ok(e4));
+        board.apply_move(e4).unwrap();
+
+        let nc6 = board.parse_move("Nc6").unwrap();
+        assert_eq!(nc6.from, Coord('b', 8));
+        assert_eq!(board.format_move(&nc6), "Nc6");
+
+        assert!(board.parse_move("e4").is_err());
+        assert!(board.parse_move("Qd4").is_err());
+        assert!(board.parse_move("").is_err());
+    }
+
+    #[test]
+    fn move_notation_details() {
+        // two red rooks on the a file and a red pawn about to promote
+        let board = Board::from_notation(
+            "rr6kr/8/8/8/rr7/8/8/3kg4/8/8/pr7/7ky r - - -").unwrap();
+
+        let moves = board.legal_moves(Player::Red);
+        let find = |from: &str, to: &str, promotion| *moves.iter()
+            .find(|m| m.from == from.parse().unwrap() && m.to == to.parse().unwrap()
+                  && m.promotion == promotion)
+            .unwrap();
+
+        let rook = find("a1", "a3", None);
+        assert_eq!(board.format_move(&rook), "R1a3");
+        assert_eq!(board.parse_move("R1a3"), Ok(rook));
+
+        let promotion = find("e11", "e12", Some(PieceType::Knight));
+        assert_eq!(board.format_move(&promotion), "e12=N");
+        assert_eq!(board.parse_move("e11e12n"), Ok(promotion));
+    }
+
+    #[test]
+    fn coords() {
+        assert_eq!("e4".parse(), Ok(Coord('e', 4)));
+        assert_eq!("L12".parse(), Ok(Coord('l', 12)));
+        assert!("e5".parse::<Coord>().is_err());
+        assert!("a13".parse::<Coord>().is_err());
+        assert_eq!(Coord::all().count(), 96);
+    }
+}
@@ -0,0 +1,134 @@
+//! Counting the positions reachable in a number of moves, the usual way to
+//! check a move generator against known numbers.
+
+use crate::board::Board;
+
+impl Board {
+    /// The number of move sequences of length `depth` the players whose
+    /// turn it is can play from here. Sequences ending early because the
+    /// game is over are not counted.
+    pub fn perft(&self, depth: u32) -> u64 {
+        perft(&mut self.clone(), depth)
+    }
+}
+
+fn perft(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if board.outcome().is_some() {
+        return 0;
+    }
+
+    let moves = board.legal_moves(board.current_player());
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mov in moves {
+        board.apply_move_unchecked(mov);
+        nodes += perft(board, depth - 1);
+        board.undo_move();
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Coord};
+
+    fn start() -> Board {
+        let mut board = Board::new();
+        board.place_pieces();
+        board
+    }
+
+    #[test]
+    fn starting_position() {
+        let board = start();
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.perft(3), 8000);
+        assert_eq!(board.perft(4), 178080);
+    }
+
+    #[test]
+    #[ignore = "takes long without optimizations"]
+    fn starting_position_deep() {
+        assert_eq!(start().perft(5), 3957237);
+    }
+
+    /// Checks the targets of the only piece on an otherwise empty board,
+    /// given as a letter pair like in the position notation.
+    fn assert_targets(piece: &str, field: &str, expected: &str) {
+        let coord: Coord = field.parse().unwrap();
+        let rank = coord.1;
+        let files = Coord::files_of_rank(rank);
+        let pos = files.iter().position(|f| *f == coord.0).unwrap();
+
+        let ranks: Vec<String> = (1 ..= 12).map(|r| if r != rank {
+            "8".to_string()
+        } else {
+            let before = if pos > 0 { pos.to_string() } else { String::new() };
+            let after = if pos < 7 { (7 - pos).to_string() } else { String::new() };
+            format!("{before}{piece}{after}")
+        }).collect();
+        let side = &piece[1 ..];
+        let board = Board::from_notation(&format!("{} {side} - - -", ranks.join("/"))).unwrap();
+
+        let sort = |mut v: Vec<Coord>| {
+            v.sort_by_key(|c| (c.1, c.0));
+            v
+        };
+        let expected = sort(expected.split_whitespace().map(|s| s.parse().unwrap()).collect());
+        assert_eq!(sort(board.get_possible_moves(coord)), expected, "{piece} on {field}");
+    }
+
+    #[test]
+    fn rooks_around_the_centre() {
+        assert_targets("rr", "d4", "d1 d2 d3 a4 b4 c4 e4 f4 g4 h4 d5 d6 d7 d8");
+        assert_targets("rr", "i5", "a5 b5 c5 d5 j5 k5 l5 i6 i7 i8 i9 i10 i11 i12");
+    }
+
+    #[test]
+    fn bishops_around_the_centre() {
+        assert_targets("br", "e4", "b1 h1 c2 g2 d3 f3 d5 c6 b7 a8 f9 i9 g10 j10 h11 k11 l12");
+        assert_targets("br", "i9", "h1 g2 f3 e4 d5 j5 c6 k6 b7 l7 a8 e10 j10 f11 k11 g12 l12");
+    }
+
+    #[test]
+    fn knights_around_the_centre() {
+        assert_targets("nr", "d5", "c3 e3 b4 f4 b6 j6 c7 i7 j9 i10");
+        assert_targets("nr", "e4", "d2 f2 c3 g3 c5 d6 g9 j9 f10 i10");
+    }
+
+    #[test]
+    fn kings_and_queens_around_the_centre() {
+        assert_targets("kr", "i5", "d4 d5 j5 d6 i6 j6 e9 i9 j9");
+        assert_targets("qr", "e9", "a1 e1 b2 e2 h2 c3 e3 g3 d4 e4 f4 i5 j6 k7 l8 f9 g9 h9 i9 \
+                                    j9 k9 l9 e10 f10 i10 e11 g11 j11 e12 h12 k12");
+    }
+
+    #[test]
+    fn pawns_crossing_the_centre() {
+        assert_targets("pr", "d4", "d5");
+        assert_targets("pr", "e4", "e9");
+        assert_targets("pr", "h4", "h9");
+        assert_targets("pg", "i5", "i9");
+        assert_targets("pg", "d5", "d4");
+        assert_targets("py", "e9", "e4");
+        assert_targets("py", "i9", "i5");
+    }
+
+    #[test]
+    fn no_duplicate_moves() {
+        for notation in ["8/8/8/8/4kr3/8/8/8/8/8/8/8 r - - -", "8/8/8/8/8/8/8/8/qr7/8/8/8 r - - -"] {
+            let board = Board::from_notation(notation).unwrap();
+            let moves = board.legal_moves(board.current_player());
+            for (i, m) in moves.iter().enumerate() {
+                assert!(! moves[.. i].contains(m), "{m} twice in {notation}");
+            }
+        }
+    }
+}
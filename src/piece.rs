@@ -0,0 +1,89 @@
+use crate::board::Board;
+use crate::indexed::IndexedBoard;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Player {
+    Red = 0,
+    Green = 1,
+    Yellow = 2,
+}
+
+impl std::fmt::Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Player::Red => write!(f, "red"),
+            Player::Green => write!(f, "green"),
+            Player::Yellow => write!(f, "yellow"),
+        }
+    }
+}
+
+impl Player {
+    pub const ALL: [Player; 3] = [Player::Red, Player::Green, Player::Yellow];
+
+    pub fn next(&self) -> Self {
+        match self {
+            Player::Red => Player::Green,
+            Player::Green => Player::Yellow,
+            Player::Yellow => Player::Red,
+        }
+    }
+
+    /// Whether the player has no legal move left, which ends its part in
+    /// the game as checkmate or stalemate.
+    pub fn is_mate(&self, board: &Board) -> bool {
+        IndexedBoard::from_board(board).is_mate(*self)
+    }
+
+    pub fn can_capture_king(&self, board: &Board) -> bool {
+        IndexedBoard::from_board(board).can_capture_king(*self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceType {
+    Pawn = 0,
+    Rook,
+    Knight,
+    Bishop,
+    Queen,
+    King
+}
+
+impl PieceType {
+    pub const ALL: [PieceType; 6] = [PieceType::Pawn, PieceType::Rook, PieceType::Knight,
+                                     PieceType::Bishop, PieceType::Queen, PieceType::King];
+
+    /// What a pawn may turn into on reaching a far end.
+    pub const PROMOTIONS: [PieceType; 4] = [PieceType::Queen, PieceType::Rook,
+                                            PieceType::Bishop, PieceType::Knight];
+}
+
+pub const PIECE_LETTERS: [char; 6] = ['p','r','n','b','q','k'];
+pub const COLOR_LETTERS: [char; 3] = ['r','g','y'];
+
+impl Player {
+    pub fn letter(&self) -> char {
+        COLOR_LETTERS[*self as usize]
+    }
+
+    pub fn from_letter(c: char) -> Option<Self> {
+        COLOR_LETTERS.iter().position(|x| *x == c).map(|i| Player::ALL[i])
+    }
+}
+
+impl PieceType {
+    pub fn letter(&self) -> char {
+        PIECE_LETTERS[*self as usize]
+    }
+
+    pub fn from_letter(c: char) -> Option<Self> {
+        PIECE_LETTERS.iter().position(|x| *x == c).map(|i| PieceType::ALL[i])
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Piece {
+    pub typ: PieceType,
+    pub player: Player
+}
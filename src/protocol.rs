@@ -0,0 +1,210 @@
+//! The messages between `threechess-server` and its clients.
+//!
+//! Every message is one line of text, a keyword followed by its arguments.
+//! Clients send
+//!
+//! - `join <seat>` to play the seat `red`, `green` or `yellow`, one per
+//!   connection,
+//! - `move <move>` to play a move of their seat, in any form
+//!   `Board::parse_move` reads.
+//!
+//! The server answers with
+//!
+//! - `rules <variant> <mate credit> <eliminated pieces>` first, naming the
+//!   rules the game is played by as `--variant`, `--mate-credit` and
+//!   `--eliminated` take them,
+//! - `clock <time control>` next when the game is played with clocks, in
+//!   the form `--clock` takes,
+//! - `joined <seat>` when a seat was taken,
+//! - `moved <seat> <move>` to everyone when a move was played, in algebraic
+//!   notation,
+//! - `position <position>` to everyone after each move, in position
+//!   notation,
+//! - `time <red> <green> <yellow> [<seat>]` with the milliseconds each seat
+//!   has left, on connecting and whenever a clock starts or stops, naming
+//!   the seat whose clock runs from then on,
+//! - `flagged <seat>` to everyone when the time of a seat ran out, which puts
+//!   it out of the game,
+//! - `error <text>` when a message was refused.
+//!
+//! Connections without a seat watch the game. A new connection first gets
+//! the starting position and every move played so far, followed by the
+//! current position, as if it had been there from the start.
+
+use std::time::Duration;
+
+use crate::clock::TimeControl;
+use crate::piece::Player;
+use crate::rules::Rules;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolError(pub String);
+
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    Join(Player),
+    Move(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Rules(Rules),
+    Clock(TimeControl),
+    Joined(Player),
+    Moved(Player, String),
+    Position(String),
+    /// The time each seat has left, and the seat whose clock runs.
+    Time([Duration; 3], Option<Player>),
+    Flagged(Player),
+    Error(String),
+}
+
+fn parse_seat(s: &str) -> Result<Player, ProtocolError> {
+    Player::ALL.into_iter().find(|p| p.to_string() == s)
+        .ok_or_else(|| ProtocolError(format!("unknown seat '{s}'")))
+}
+
+/// Splits a line into its keyword and the rest, which must not be empty.
+fn split(line: &str) -> Result<(&str, &str), ProtocolError> {
+    match line.trim().split_once(' ') {
+        Some((keyword, rest)) if ! rest.trim().is_empty() => Ok((keyword, rest.trim())),
+        _ => Err(ProtocolError(format!("invalid message '{}'", line.trim()))),
+    }
+}
+
+impl std::fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ClientMessage::Join(seat) => write!(f, "join {seat}"),
+            ClientMessage::Move(mov) => write!(f, "move {mov}"),
+        }
+    }
+}
+
+impl std::str::FromStr for ClientMessage {
+    type Err = ProtocolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split(s)? {
+            ("join", seat) => Ok(ClientMessage::Join(parse_seat(seat)?)),
+            ("move", mov) => Ok(ClientMessage::Move(mov.to_string())),
+            (keyword, _) => Err(ProtocolError(format!("unknown message '{keyword}'"))),
+        }
+    }
+}
+
+impl std::fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ServerMessage::Rules(rules) => write!(f, "rules {} {} {}", rules.variant,
+                                                  rules.mate_credit, rules.eliminated_pieces),
+            ServerMessage::Clock(control) => write!(f, "clock {control}"),
+            ServerMessage::Joined(seat) => write!(f, "joined {seat}"),
+            ServerMessage::Moved(seat, mov) => write!(f, "moved {seat} {mov}"),
+            ServerMessage::Position(position) => write!(f, "position {position}"),
+            ServerMessage::Time(left, running) => {
+                let [red, green, yellow] = left.map(|t| t.as_millis());
+                write!(f, "time {red} {green} {yellow}")?;
+                match running {
+                    Some(seat) => write!(f, " {seat}"),
+                    None => Ok(()),
+                }
+            },
+            ServerMessage::Flagged(seat) => write!(f, "flagged {seat}"),
+            ServerMessage::Error(text) => write!(f, "error {text}"),
+        }
+    }
+}
+
+impl std::str::FromStr for ServerMessage {
+    type Err = ProtocolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split(s)? {
+            ("rules", rest) => match rest.split_whitespace().collect::<Vec<_>>()[..] {
+                [variant, mate_credit, eliminated_pieces] => Ok(ServerMessage::Rules(Rules {
+                    variant: variant.parse().map_err(ProtocolError)?,
+                    mate_credit: mate_credit.parse().map_err(ProtocolError)?,
+                    eliminated_pieces: eliminated_pieces.parse().map_err(ProtocolError)?,
+                })),
+                _ => Err(ProtocolError(format!("invalid rules '{rest}'"))),
+            },
+            ("clock", control) => Ok(ServerMessage::Clock(control.parse().map_err(ProtocolError)?)),
+            ("joined", seat) => Ok(ServerMessage::Joined(parse_seat(seat)?)),
+            ("moved", rest) => {
+                let (seat, mov) = split(rest)?;
+                Ok(ServerMessage::Moved(parse_seat(seat)?, mov.to_string()))
+            },
+            ("position", position) => Ok(ServerMessage::Position(position.to_string())),
+            ("time", rest) => {
+                let words: Vec<_> = rest.split_whitespace().collect();
+                let invalid = || ProtocolError(format!("invalid time '{rest}'"));
+                let (times, running) = match words[..] {
+                    [a, b, c] => ([a, b, c], None),
+                    [a, b, c, seat] => ([a, b, c], Some(parse_seat(seat)?)),
+                    _ => return Err(invalid()),
+                };
+                let mut left = [Duration::ZERO; 3];
+                for (t, s) in left.iter_mut().zip(times) {
+                    *t = Duration::from_millis(s.parse().map_err(|_| invalid())?);
+                }
+                Ok(ServerMessage::Time(left, running))
+            },
+            ("flagged", seat) => Ok(ServerMessage::Flagged(parse_seat(seat)?)),
+            ("error", text) => Ok(ServerMessage::Error(text.to_string())),
+            (keyword, _) => Err(ProtocolError(format!("unknown message '{keyword}'"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_read_back() {
+        for m in [ClientMessage::Join(Player::Green), ClientMessage::Move("Nf3".to_string())] {
+            assert_eq!(m.to_string().parse(), Ok(m));
+        }
+        for m in [
+            ServerMessage::Rules(Rules::default()),
+            ServerMessage::Rules(Rules {
+                variant: crate::variant::Variant::Chess960(100),
+                mate_credit: crate::rules::MateCredit::Checker,
+                eliminated_pieces: crate::rules::EliminatedPieces::Captured,
+            }),
+            ServerMessage::Clock("5+3".parse().unwrap()),
+            ServerMessage::Joined(Player::Yellow),
+            ServerMessage::Time([Duration::from_millis(299_950), Duration::from_secs(300), Duration::ZERO],
+                                Some(Player::Green)),
+            ServerMessage::Time([Duration::from_secs(1); 3], None),
+            ServerMessage::Flagged(Player::Yellow),
+            ServerMessage::Moved(Player::Red, "e4".to_string()),
+            ServerMessage::Position("8/8/8/8/8/8/8/8/8/8/8/8 r - - -".to_string()),
+            ServerMessage::Error("it is not your turn".to_string()),
+        ] {
+            assert_eq!(m.to_string().parse(), Ok(m));
+        }
+    }
+
+    #[test]
+    fn bad_messages() {
+        assert!("join blue".parse::<ClientMessage>().is_err());
+        assert!("move".parse::<ClientMessage>().is_err());
+        assert!("resign now".parse::<ClientMessage>().is_err());
+        assert!("moved red".parse::<ServerMessage>().is_err());
+        assert!("rules standard mover".parse::<ServerMessage>().is_err());
+        assert!("rules standard mover gone".parse::<ServerMessage>().is_err());
+        assert!("time 1 2".parse::<ServerMessage>().is_err());
+        assert!("time 1 2 x".parse::<ServerMessage>().is_err());
+        assert!("time 1 2 3 blue".parse::<ServerMessage>().is_err());
+    }
+}
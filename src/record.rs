@@ -0,0 +1,400 @@
+//! A PGN-like file format for whole games.
+//!
+//! ```text
+//! [Event "Casual game"]
+//! [Date "2026.10.17"]
+//! [Red "Anna"]
+//! [Green "Ben"]
+//! [Yellow "Cleo"]
+//! [Result "3-1-2"]
+//!
+//! 1. e4 d5 l9 2. Nf3 Nc6 ... 3-1-2
+//! ```
+//!
+//! Tag pairs are followed by the moves in the notation of `Board::format_move`.
+//! A new move number starts whenever the turn comes round to an earlier
+//! player again. Players leaving other than by mate stand between the moves
+//! where they left, as `<colour>-resigns`, `<colour>-flagged` when out of
+//! time, or `draw-agreed` for everyone still in. The result gives the place
+//! each colour finished in, players who drew sharing theirs, or is `*` while
+//! the game goes on.
+//!
+//! Games that do not start from the standard position carry it in a
+//! `Position` tag, written by `Board::to_notation`, and games of another
+//! variant than the standard one name it in a `Variant` tag. House rules
+//! other than the defaults go in a `MateCredit` tag, `mover` or `checker`,
+//! and an `Eliminated` tag, `neutral`, `removed` or `captured`.
+
+use crate::board::Board;
+use crate::moves::Move;
+use crate::notation::NotationError;
+use crate::piece::Player;
+use crate::rules::{EliminatedPieces, MateCredit, PlayerStatus, Rules};
+use crate::variant::Variant;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameRecord {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+}
+
+fn starting_position() -> Board {
+    let mut board = Board::new();
+    board.place_pieces();
+    board
+}
+
+/// The result of the game on `board`, as written in a record.
+pub fn result_string(board: &Board) -> String {
+    if board.outcome().is_none() {
+        return "*".to_string();
+    }
+    let ranking = board.ranking();
+    Player::ALL.map(|p| ranking.iter().find(|(_, x)| *x == p).unwrap().0.to_string()).join("-")
+}
+
+/// The movetext token for `player` leaving with `status`, if it is one a
+/// move does not bring about.
+fn departure_token(player: Player, status: PlayerStatus) -> Option<String> {
+    match status {
+        PlayerStatus::Resigned => Some(format!("{player}-resigns")),
+        PlayerStatus::TimedOut => Some(format!("{player}-flagged")),
+        PlayerStatus::Drawn => Some("draw-agreed".to_string()),
+        _ => None,
+    }
+}
+
+/// Reads a token of `departure_token`, the player being `None` for a draw.
+fn parse_departure(token: &str) -> Option<(Option<Player>, PlayerStatus)> {
+    if token == "draw-agreed" {
+        return Some((None, PlayerStatus::Drawn));
+    }
+    let (player, status) = token.split_once('-')?;
+    let player = Player::ALL.into_iter().find(|p| p.to_string() == player)?;
+    match status {
+        "resigns" => Some((Some(player), PlayerStatus::Resigned)),
+        "flagged" => Some((Some(player), PlayerStatus::TimedOut)),
+        _ => None,
+    }
+}
+
+/// Also takes the older results with a `1` for the winner and `0` for the
+/// others.
+fn is_result(s: &str) -> bool {
+    s == "*" || (s.len() == 5 && s.split('-').all(|x| matches!(x, "0" | "1" | "2" | "3")))
+}
+
+impl GameRecord {
+    /// Records the moves played on `board` so far, and the players who
+    /// resigned, ran out of time or agreed to a draw in between.
+    pub fn from_board(board: &Board) -> Self {
+        let mut start = board.clone();
+        while start.undo_move().is_some() {}
+
+        let mut record = GameRecord::default();
+        if start.to_notation() != starting_position().to_notation() {
+            record.set_tag("Position", &start.to_notation());
+        }
+        let rules = board.rules();
+        if rules.variant != Variant::Standard {
+            record.set_tag("Variant", &rules.variant.to_string());
+        }
+        if rules.mate_credit != MateCredit::default() {
+            record.set_tag("MateCredit", &rules.mate_credit.to_string());
+        }
+        if rules.eliminated_pieces != EliminatedPieces::default() {
+            record.set_tag("Eliminated", &rules.eliminated_pieces.to_string());
+        }
+        record.set_tag("Result", &result_string(board));
+
+        let departures: Vec<_> = board.departures().collect();
+        let moves: Vec<_> = board.history().copied().collect();
+        for i in 0 ..= moves.len() {
+            while start.departures().count() < board.departures_before(i) {
+                let (player, status) = departures[start.departures().count()];
+                let Some(token) = departure_token(player, status) else { break };
+                record.moves.push(token);
+                match status {
+                    PlayerStatus::Drawn => start.agree_draw(),
+                    _ => start.eliminate(player, status),
+                }
+            }
+            if let Some(mov) = moves.get(i) {
+                record.moves.push(start.format_move(mov));
+                start.apply_move_unchecked(*mov);
+            }
+        }
+        record
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(t) => t.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Plays the recorded moves from the starting position, and has players
+    /// leave where the record says so, returning the final position with the
+    /// moves in its history.
+    pub fn replay(&self) -> Result<Board, NotationError> {
+        let variant = match self.tag("Variant") {
+            Some(v) => v.parse().map_err(NotationError)?,
+            None => Variant::Standard,
+        };
+        let mate_credit = match self.tag("MateCredit") {
+            Some(c) => c.parse().map_err(NotationError)?,
+            None => MateCredit::default(),
+        };
+        let eliminated_pieces = match self.tag("Eliminated") {
+            Some(e) => e.parse().map_err(NotationError)?,
+            None => EliminatedPieces::default(),
+        };
+        let mut board = match self.tag("Position") {
+            Some(p) => Board::from_notation(p)?,
+            None => variant.setup(),
+        };
+        board.set_rules(Rules { variant, mate_credit, eliminated_pieces });
+
+        for (i, s) in self.moves.iter().enumerate() {
+            match parse_departure(s) {
+                Some((None, _)) => board.agree_draw(),
+                Some((Some(player), status)) => {
+                    if board.status(player) != PlayerStatus::Playing {
+                        return Err(NotationError(format!("move {}: {player} is not playing", i + 1)));
+                    }
+                    board.eliminate(player, status);
+                },
+                None => {
+                    let mov = board.parse_move(s)
+                        .map_err(|e| NotationError(format!("move {}: {e}", i + 1)))?;
+                    board.apply_move_unchecked(mov);
+                },
+            }
+        }
+        Ok(board)
+    }
+
+    /// The recorded moves, checked for legality.
+    pub fn parse_moves(&self) -> Result<Vec<Move>, NotationError> {
+        Ok(self.replay()?.history().copied().collect())
+    }
+
+    pub fn parse(s: &str) -> Result<Self, NotationError> {
+        let mut record = GameRecord::default();
+        let mut movetext = String::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                let tag = line.strip_prefix('[').and_then(|t| t.strip_suffix(']'))
+                    .and_then(|t| t.split_once(' '))
+                    .and_then(|(n, v)| Some((n, v.trim().strip_prefix('"')?.strip_suffix('"')?)));
+                let (name, value) = match tag {
+                    Some(x) => x,
+                    None => return Err(NotationError(format!("invalid tag '{line}'"))),
+                };
+                record.set_tag(name, &value.replace("\\\"", "\""));
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+
+        // comments in braces are skipped
+        let mut depth = 0;
+        let movetext: String = movetext.chars().filter(|c| {
+            match c {
+                '{' => depth += 1,
+                '}' => { depth -= 1; return false },
+                _ => (),
+            }
+            depth == 0
+        }).collect();
+
+        for token in movetext.split_whitespace() {
+            if is_result(token) {
+                break;
+            }
+            let token = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+            if token.is_empty() {
+                continue;
+            }
+            record.moves.push(token.to_string());
+        }
+
+        Ok(record)
+    }
+}
+
+impl std::fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{name} \"{}\"]", value.replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+
+        // move numbers follow the turn order, which only the replay knows
+        // once players start dropping out
+        let players: Vec<_> = self.replay()
+            .map(|b| b.history().map(|m| m.piece.player).collect())
+            .unwrap_or_default();
+
+        let mut tokens = vec![];
+        let mut number = 0;
+        let mut played = 0;
+        for mov in &self.moves {
+            if parse_departure(mov).is_some() {
+                tokens.push(mov.clone());
+                continue;
+            }
+            let new_round = played == 0 || matches!((players.get(played - 1), players.get(played)),
+                                                    (Some(l), Some(p)) if *p as usize <= *l as usize);
+            if new_round {
+                number += 1;
+                tokens.push(format!("{number}."));
+            }
+            tokens.push(mov.clone());
+            played += 1;
+        }
+        tokens.push(self.tag("Result").unwrap_or("*").to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if ! line.is_empty() && line.len() + token.len() >= 80 {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            if ! line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameOutcome;
+
+    #[test]
+    fn round_trip() {
+        let mut board = starting_position();
+        for s in ["e4", "d5", "l9", "exd5"] {
+            let mov = board.parse_move(s).unwrap();
+            board.apply_move(mov).unwrap();
+        }
+
+        let mut record = GameRecord::from_board(&board);
+        record.set_tag("Red", "Anna \"the rook\"");
+        assert_eq!(record.moves, ["e4", "d5", "l9", "exd5"]);
+
+        let text = record.to_string();
+        assert!(text.contains("[Red \"Anna \\\"the rook\\\"\"]"), "{text}");
+        assert!(text.ends_with("1. e4 d5 l9 2. exd5 *\n"), "{text}");
+
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.parse_moves().unwrap(), board.history().copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn custom_position_and_comments() {
+        let text = "[Position \"rr6kr/8/8/8/8/8/8/3kg4/8/8/8/7ky g - - -\"]\n\
+                    \n\
+                    1. Kc8 {avoiding the rook} Kk12 2. Ra2 *";
+        let record = GameRecord::parse(text).unwrap();
+        assert_eq!(record.moves, ["Kc8", "Kk12", "Ra2"]);
+
+        let board = record.replay().unwrap();
+        assert_eq!(board.history().count(), 3);
+        assert_eq!(GameRecord::from_board(&board).moves, record.moves);
+
+        let illegal = GameRecord::parse("1. e5 *").unwrap();
+        assert!(illegal.replay().is_err());
+    }
+
+    #[test]
+    fn variant_tag() {
+        let mut board = Variant::Chess960(0).setup();
+        board.apply_move(board.legal_moves(Player::Red)[0]).unwrap();
+        let record = GameRecord::from_board(&board);
+        assert_eq!(record.tag("Variant"), Some("chess960-0"));
+
+        let replayed = GameRecord::parse(&record.to_string()).unwrap().replay().unwrap();
+        assert_eq!(replayed.rules().variant, Variant::Chess960(0));
+        assert_eq!(replayed.to_notation(), board.to_notation());
+    }
+
+    #[test]
+    fn rules_tags() {
+        let notation = "qyrr5kr/8/8/nr7/8/8/8/kg7/8/8/8/rg6ky r - - -";
+        let mut board = Board::from_notation(notation).unwrap();
+        board.set_rules(Rules { eliminated_pieces: EliminatedPieces::Captured, ..board.rules() });
+        for text in ["Nc5#", "Kl11", "Re11+"] {
+            board.apply_move(board.parse_move(text).unwrap()).unwrap();
+        }
+        let record = GameRecord::from_board(&board);
+        assert_eq!(record.tag("Eliminated"), Some("captured"));
+        assert_eq!(record.tag("MateCredit"), None);
+        let replayed = GameRecord::parse(&record.to_string()).unwrap().replay().unwrap();
+        assert_eq!(replayed.rules(), board.rules());
+        assert_eq!(replayed.to_notation(), board.to_notation());
+
+        let mut board = Board::from_notation(notation).unwrap();
+        board.set_rules(Rules { mate_credit: MateCredit::Checker, ..board.rules() });
+        board.apply_move(board.parse_move("Nc5#").unwrap()).unwrap();
+        let record = GameRecord::from_board(&board);
+        assert_eq!(record.tag("MateCredit"), Some("checker"));
+        let replayed = GameRecord::parse(&record.to_string()).unwrap().replay().unwrap();
+        assert_eq!(replayed.status(Player::Green), PlayerStatus::Checkmated { by: Some(Player::Yellow) });
+
+        assert!(GameRecord::parse("[Eliminated \"kept\"]\n\n*").unwrap().replay().is_err());
+    }
+
+    #[test]
+    fn results() {
+        let mut board = starting_position();
+        assert_eq!(result_string(&board), "*");
+        board.eliminate(Player::Red, PlayerStatus::Resigned);
+        board.agree_draw();
+        assert_eq!(result_string(&board), "3-1-1");
+
+        let record = GameRecord::parse("1. e4 0-1-0").unwrap();
+        assert_eq!(record.moves, ["e4"]);
+    }
+
+    #[test]
+    fn departures_between_moves() {
+        let mut board = starting_position();
+        board.apply_move(board.parse_move("e4").unwrap()).unwrap();
+        board.eliminate(Player::Green, PlayerStatus::TimedOut);
+        board.apply_move(board.parse_move("l9").unwrap()).unwrap();
+        board.eliminate(Player::Red, PlayerStatus::Resigned);
+
+        let record = GameRecord::from_board(&board);
+        assert_eq!(record.moves, ["e4", "green-flagged", "l9", "red-resigns"]);
+        let text = record.to_string();
+        assert!(text.ends_with("1. e4 green-flagged l9 red-resigns 2-3-1\n"), "{text}");
+
+        let replayed = GameRecord::parse(&text).unwrap().replay().unwrap();
+        assert_eq!(replayed.outcome(), Some(GameOutcome::Won(Player::Yellow)));
+        assert_eq!(replayed.to_notation(), board.to_notation());
+        assert_eq!(result_string(&replayed), "2-3-1");
+
+        let mut board = starting_position();
+        board.apply_move(board.parse_move("e4").unwrap()).unwrap();
+        board.agree_draw();
+        let replayed = GameRecord::parse(&GameRecord::from_board(&board).to_string()).unwrap()
+            .replay().unwrap();
+        assert_eq!(replayed.outcome(), Some(GameOutcome::DrawAgreed));
+
+        assert!(GameRecord::parse("1. e4 red-resigns red-resigns *").unwrap().replay().is_err());
+    }
+}
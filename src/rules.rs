@@ -0,0 +1,157 @@
+//! How players drop out of a game, how it ends, and the house rules that
+//! decide either.
+//!
+//! A player without a legal move is checkmated when in check and
+//! stalemated otherwise; either way it is out, as is a player who resigns,
+//! runs out of time or loses its king. The game is won by the one player
+//! left, or the first to reach the hill where the variant has one. It is
+//! drawn when the players still in agree to one, a position comes up for the
+//! third time or `FIFTY_MOVES` went by without a capture or pawn move.
+//! Players are then ranked by how long they stayed in, those drawing
+//! sharing a place.
+
+use crate::piece::Player;
+use crate::variant::Variant;
+
+/// How often a position has to occur for the game to be drawn.
+pub const REPETITIONS: usize = 3;
+
+/// The fifty-move rule counts the moves of all three players, fifty each.
+pub const FIFTY_MOVES: usize = 150;
+
+/// Where a player stands in the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerStatus {
+    Playing,
+    /// Left without a legal move while in check, or with its king taken.
+    /// `by` is the player credited with the mate, see `MateCredit`.
+    Checkmated { by: Option<Player> },
+    /// Left without a legal move but not in check.
+    Stalemated,
+    Resigned,
+    TimedOut,
+    /// Still in when the game was agreed drawn.
+    Drawn,
+}
+
+impl PlayerStatus {
+    /// Whether the player dropped out before the game ended.
+    pub fn is_out(&self) -> bool {
+        ! matches!(self, PlayerStatus::Playing | PlayerStatus::Drawn)
+    }
+}
+
+impl std::fmt::Display for PlayerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PlayerStatus::Playing => write!(f, "is playing"),
+            PlayerStatus::Checkmated { by: Some(by) } => write!(f, "was mated by {by}"),
+            PlayerStatus::Checkmated { by: None } => write!(f, "is mate"),
+            PlayerStatus::Stalemated => write!(f, "is stalemated"),
+            PlayerStatus::Resigned => write!(f, "resigned"),
+            PlayerStatus::TimedOut => write!(f, "lost on time"),
+            PlayerStatus::Drawn => write!(f, "agreed to a draw"),
+        }
+    }
+}
+
+/// How a finished game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOutcome {
+    /// The last player left in the game, or the first on the hill.
+    Won(Player),
+    /// The players still in agreed to a draw.
+    DrawAgreed,
+    /// The same position came up `REPETITIONS` times.
+    Repetition,
+    /// `FIFTY_MOVES` were played without a capture or pawn move.
+    FiftyMoves,
+}
+
+impl std::fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameOutcome::Won(player) => write!(f, "{player} has won"),
+            GameOutcome::DrawAgreed => write!(f, "the game is drawn"),
+            GameOutcome::Repetition => write!(f, "the game is drawn by repetition"),
+            GameOutcome::FiftyMoves => write!(f, "the game is drawn by the fifty-move rule"),
+        }
+    }
+}
+
+/// Who is credited with a checkmate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MateCredit {
+    /// The player whose move left the mated one without a move.
+    #[default]
+    Mover,
+    /// The player whose piece gives the check, the mover when both do.
+    Checker,
+}
+
+/// What becomes of the pieces of a player who is out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EliminatedPieces {
+    /// They stay where they are as obstacles, giving no check and open to
+    /// capture.
+    #[default]
+    Neutral,
+    /// They are taken off the board.
+    Removed,
+    /// The player credited with the mate takes them over, all but the king.
+    /// Without a mate to credit they are removed.
+    Captured,
+}
+
+/// The house rules a game is played by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub variant: Variant,
+    pub mate_credit: MateCredit,
+    pub eliminated_pieces: EliminatedPieces,
+}
+
+impl std::fmt::Display for MateCredit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MateCredit::Mover => write!(f, "mover"),
+            MateCredit::Checker => write!(f, "checker"),
+        }
+    }
+}
+
+impl std::fmt::Display for EliminatedPieces {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EliminatedPieces::Neutral => write!(f, "neutral"),
+            EliminatedPieces::Removed => write!(f, "removed"),
+            EliminatedPieces::Captured => write!(f, "captured"),
+        }
+    }
+}
+
+impl std::str::FromStr for MateCredit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mover" => Ok(MateCredit::Mover),
+            "checker" => Ok(MateCredit::Checker),
+            _ => Err(format!("unknown mate credit '{s}', expected mover or checker")),
+        }
+    }
+}
+
+impl std::str::FromStr for EliminatedPieces {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            // still accepted from before the pieces became neutral obstacles
+            "neutral" | "remain" => Ok(EliminatedPieces::Neutral),
+            "removed" => Ok(EliminatedPieces::Removed),
+            "captured" => Ok(EliminatedPieces::Captured),
+            _ => Err(format!("unknown handling of pieces '{s}', expected neutral, removed or captured")),
+        }
+    }
+}
@@ -1,14 +1,20 @@
 use std::io::Read;
-use crate::backend::{self, Color, Event, MouseButton};
+use crate::backend::{self, Color, Event, ImageHandle, Key, Modifiers, MouseButton};
 
-use sdl2::{render::{Canvas, TextureCreator}, video::{Window, WindowContext}, EventPump, ttf::{Font, Sdl2TtfContext}, rect::Rect};
+use sdl2::{render::{Canvas, Texture, TextureCreator}, video::{Window, WindowContext}, EventPump, ttf::{Font, Sdl2TtfContext}, rect::Rect};
 use sdl2::image::LoadTexture;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::rect::FPoint;
+use sdl2::render::Vertex;
 
 pub struct SdlBackend<'a, 'b> {
     canvas: Canvas<Window>,
     event_pump: EventPump,
+    // borrows from `texture_creator`, so it has to be dropped before it
+    images: Vec<Texture<'static>>,
     texture_creator: TextureCreator<WindowContext>,
     ttf: TTFWrapper<'a, 'b>,
+    start: std::time::Instant,
 }
 
 struct TTFWrapper<'a, 'b> {
@@ -26,13 +32,13 @@ impl<'a, 'b: 'a> TTFWrapper<'a, 'b> {
 
     fn add_font(&mut self) {
         self.font = unsafe {
-            std::mem::transmute::<_, Option<Font<'static, 'static>>>(
+            std::mem::transmute::<Option<Font<'_, '_>>, Option<Font<'static, 'static>>>(
                 self.ctx.load_font("./FiraCode.ttf", 18).ok()
             )
         };
     }
 
-    fn font(&'a self) -> &'a Font {
+    fn font(&'a self) -> &'a Font<'a, 'b> {
         self.font.as_ref().unwrap()
     }
 }
@@ -61,8 +67,10 @@ impl<'a, 'b: 'a> SdlBackend<'a, 'b> {
         let mut obj = Self {
             canvas,
             event_pump,
+            images: vec![],
             texture_creator,
             ttf,
+            start: std::time::Instant::now(),
         };
 
         obj.ttf.add_font();
@@ -71,6 +79,37 @@ impl<'a, 'b: 'a> SdlBackend<'a, 'b> {
 
 }
 
+fn mouse_button_from_sdl(button: sdl2::mouse::MouseButton) -> MouseButton {
+    match button {
+        sdl2::mouse::MouseButton::Left => MouseButton::Left,
+        _ => MouseButton::Unknown,
+    }
+}
+
+fn key_from_sdl(keycode: Option<Keycode>) -> Key {
+    match keycode {
+        Some(Keycode::Return) | Some(Keycode::KpEnter) => Key::Enter,
+        Some(Keycode::Escape) => Key::Escape,
+        Some(Keycode::Backspace) => Key::Backspace,
+        Some(Keycode::Tab) => Key::Tab,
+        Some(Keycode::Left) => Key::Left,
+        Some(Keycode::Right) => Key::Right,
+        Some(Keycode::Up) => Key::Up,
+        Some(Keycode::Down) => Key::Down,
+        // SDL key codes of printable keys are their characters
+        Some(k) if (0x20 .. 0x7f).contains(&k.into_i32()) => Key::Char(k.into_i32() as u8 as char),
+        _ => Key::Unknown,
+    }
+}
+
+fn modifiers_from_sdl(keymod: Mod) -> Modifiers {
+    Modifiers {
+        ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+        shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+        alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+    }
+}
+
 fn color_to_sdl_color(color: Color) -> sdl2::pixels::Color {
     sdl2::pixels::Color::RGB(color.0, color.1, color.2)
 }
@@ -97,18 +136,43 @@ impl<'a, 'b> backend::Backend for SdlBackend<'a, 'b> {
             .flat_map(|x| {
                 match x {
                     sdl2::event::Event::Quit { .. } => Some(Event::Quit),
+                    sdl2::event::Event::MouseButtonDown { mouse_btn, x, y, .. } =>
+                        Some(Event::MouseButtonDown(mouse_button_from_sdl(mouse_btn), x, y)),
                     sdl2::event::Event::MouseButtonUp { mouse_btn, x, y, .. } =>
-                        Some(Event::MouseButtonUp(match mouse_btn {
-                            sdl2::mouse::MouseButton::Left => MouseButton::Left,
-                            _ => MouseButton::Unknown,
-                        }, x, y)),
+                        Some(Event::MouseButtonUp(mouse_button_from_sdl(mouse_btn), x, y)),
+                    sdl2::event::Event::MouseMotion { x, y, .. } =>
+                        Some(Event::MouseMotion(x, y)),
+                    sdl2::event::Event::KeyDown { keycode, keymod, .. } =>
+                        Some(Event::KeyDown(key_from_sdl(keycode), modifiers_from_sdl(keymod))),
+                    sdl2::event::Event::KeyUp { keycode, keymod, .. } =>
+                        Some(Event::KeyUp(key_from_sdl(keycode), modifiers_from_sdl(keymod))),
                     _ => None,
                 }
             }).collect()
     }
 
-    fn draw_point(&mut self, x: i32, y: i32) {
-        self.canvas.draw_point((x, y)).unwrap();
+    fn fill_polygon(&mut self, points: &[(i32, i32)]) {
+        let color = self.canvas.draw_color();
+        let vertices: Vec<_> = points.iter().map(|p| Vertex {
+            position: FPoint::new(p.0 as f32, p.1 as f32),
+            color,
+            tex_coord: FPoint::new(0.0, 0.0),
+        }).collect();
+        // a fan of triangles around the first corner
+        let indices: Vec<[u32; 3]> = (1 .. points.len().saturating_sub(1) as u32)
+            .map(|i| [0, i, i + 1])
+            .collect();
+        self.canvas.render_geometry(&vertices, None, &indices).unwrap();
+    }
+
+    fn fill_circle(&mut self, center: (i32, i32), radius: i32) {
+        let segments = (radius * 2).clamp(8, 90);
+        let points: Vec<_> = (0 .. segments).map(|i| {
+            let angle = i as f64 * std::f64::consts::TAU / segments as f64;
+            (center.0 + (radius as f64 * angle.cos()).round() as i32,
+             center.1 + (radius as f64 * angle.sin()).round() as i32)
+        }).collect();
+        self.fill_polygon(&points);
     }
 
     fn text_size(&self, string: &str) -> (u32, u32) {
@@ -126,16 +190,27 @@ impl<'a, 'b> backend::Backend for SdlBackend<'a, 'b> {
         self.canvas.copy(&texture, None, Some(target)).unwrap();
     }
 
-    fn render_png_data(&mut self, data: &[u8], x: i32, y: i32, w: i32, h: i32) {
-        let text = self.texture_creator.load_texture_bytes(data).unwrap();
+    fn load_image(&mut self, data: &[u8]) -> ImageHandle {
+        let texture = self.texture_creator.load_texture_bytes(data).unwrap();
+        self.images.push(unsafe {
+            std::mem::transmute::<Texture<'_>, Texture<'static>>(texture)
+        });
+        ImageHandle(self.images.len() - 1)
+    }
+
+    fn draw_image(&mut self, image: ImageHandle, x: i32, y: i32, w: i32, h: i32) {
         let target = Rect::new(x, y, w as u32, h as u32);
-        self.canvas.copy(&text, None, Some(target)).unwrap();
+        self.canvas.copy(&self.images[image.0], None, Some(target)).unwrap();
     }
 
     fn present(&mut self) {
         self.canvas.present();
     }
 
+    fn ticks(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
     fn log(string: String) {
         println!("{}", string);
     }
@@ -150,4 +225,10 @@ impl<'a, 'b> backend::Backend for SdlBackend<'a, 'b> {
         f.read_to_end(&mut data).unwrap();
         data
     }
+
+    fn write_file(&self, path: &str, data: &[u8]) {
+        if let Err(e) = std::fs::write(path, data) {
+            eprintln!("could not write {path}: {e}");
+        }
+    }
 }
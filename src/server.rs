@@ -0,0 +1,464 @@
+//! A server holding the one game everyone connected to it plays. Moves are
+//! checked against the rules before they are passed on, so clients only
+//! ever show positions the server agreed to. See `protocol` for the
+//! messages, which travel as lines over plain TCP or, for browsers, as
+//! WebSocket text messages.
+//!
+//! A game played with clocks has them kept here too. They start once every
+//! seat is taken, and a seat whose time runs out is put out of the game.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::clock::{Clocks, TimeControl};
+use crate::moves::Move;
+use crate::piece::Player;
+use crate::protocol::{ClientMessage, ServerMessage};
+use crate::rules::PlayerStatus;
+use crate::websocket;
+
+/// How often the clocks are looked at for a fallen flag.
+const FLAG_CHECK: Duration = Duration::from_millis(50);
+
+#[derive(Clone)]
+pub struct Server {
+    game: Arc<Mutex<Game>>,
+}
+
+struct Game {
+    /// Where the game began, for catching up connections joining later.
+    start: Board,
+    board: Board,
+    /// The connection playing each seat, in the order of `Player::ALL`.
+    seats: [Option<usize>; 3],
+    clients: HashMap<usize, Sender<ServerMessage>>,
+    next_id: usize,
+    clocks: Option<Clocks>,
+    /// What the clocks count from.
+    epoch: Instant,
+}
+
+impl Game {
+    /// Adds a connection, watching until it joins a seat. It first gets the
+    /// rules and the game so far as if it had been there from the start.
+    fn connect(&mut self, sender: Sender<ServerMessage>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut board = self.start.clone();
+        let _ = sender.send(ServerMessage::Rules(board.rules()));
+        if let Some(clocks) = &self.clocks {
+            let _ = sender.send(ServerMessage::Clock(clocks.control));
+        }
+        let _ = sender.send(ServerMessage::Position(board.to_notation()));
+        for mov in self.board.history() {
+            let _ = sender.send(ServerMessage::Moved(mov.piece.player, board.format_move(mov)));
+            board.apply_move_unchecked(*mov);
+        }
+        if self.board.history().next().is_some() {
+            let _ = sender.send(ServerMessage::Position(self.board.to_notation()));
+        }
+        if let Some(time) = self.time() {
+            let _ = sender.send(time);
+        }
+
+        self.clients.insert(id, sender);
+        id
+    }
+
+    fn disconnect(&mut self, id: usize) {
+        self.clients.remove(&id);
+        for seat in &mut self.seats {
+            if *seat == Some(id) {
+                *seat = None;
+            }
+        }
+    }
+
+    fn send(&self, id: usize, message: ServerMessage) {
+        if let Some(sender) = self.clients.get(&id) {
+            let _ = sender.send(message);
+        }
+    }
+
+    fn broadcast(&self, message: ServerMessage) {
+        for sender in self.clients.values() {
+            let _ = sender.send(message.clone());
+        }
+    }
+
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+
+    /// The `time` message for the clocks as they are now.
+    fn time(&self) -> Option<ServerMessage> {
+        self.clocks.as_ref().map(|c| ServerMessage::Time(c.times(self.now()), c.running()))
+    }
+
+    /// Runs the clock of the player to move, or stops them all once the
+    /// game is over, and tells everyone.
+    fn switch_clocks(&mut self) {
+        let now = self.now();
+        let Some(clocks) = &mut self.clocks else { return };
+        if self.board.outcome().is_some() {
+            clocks.stop(now);
+        } else {
+            clocks.start(self.board.current_player(), now);
+        }
+        if let Some(time) = self.time() {
+            self.broadcast(time);
+        }
+    }
+
+    /// Puts the player to move out of the game once its time is up.
+    fn check_time(&mut self) {
+        let now = self.now();
+        let flagged = self.clocks.as_ref().and_then(|c| c.running().filter(|p| c.flagged(*p, now)));
+        let Some(player) = flagged else { return };
+
+        self.board.eliminate(player, PlayerStatus::TimedOut);
+        self.broadcast(ServerMessage::Flagged(player));
+        self.broadcast(ServerMessage::Position(self.board.to_notation()));
+        self.switch_clocks();
+    }
+
+    fn handle(&mut self, id: usize, message: ClientMessage) {
+        match message {
+            ClientMessage::Join(seat) => match self.seats[seat as usize] {
+                Some(other) if other != id => {
+                    self.send(id, ServerMessage::Error(format!("{seat} is already taken")));
+                },
+                None if self.seats.contains(&Some(id)) => {
+                    self.send(id, ServerMessage::Error("you already have a seat".to_string()));
+                },
+                _ => {
+                    self.seats[seat as usize] = Some(id);
+                    self.send(id, ServerMessage::Joined(seat));
+
+                    let waiting = self.clocks.as_ref().is_some_and(|c| c.running().is_none());
+                    if waiting && ! self.seats.contains(&None) && self.board.outcome().is_none() {
+                        self.switch_clocks();
+                    }
+                },
+            },
+            ClientMessage::Move(text) => {
+                let player = self.board.current_player();
+                if self.board.outcome().is_some() {
+                    self.send(id, ServerMessage::Error("the game is over".to_string()));
+                } else if ! self.seats.contains(&Some(id)) {
+                    self.send(id, ServerMessage::Error("spectators cannot move".to_string()));
+                } else if self.seats[player as usize] != Some(id) {
+                    self.send(id, ServerMessage::Error("it is not your turn".to_string()));
+                } else {
+                    match self.board.parse_move(&text) {
+                        Ok(mov) => self.play(player, mov),
+                        Err(e) => self.send(id, ServerMessage::Error(e.to_string())),
+                    }
+                }
+            },
+        }
+    }
+
+    fn play(&mut self, player: Player, mov: Move) {
+        let written = self.board.format_move(&mov);
+        self.board.apply_move(mov).unwrap();
+        self.broadcast(ServerMessage::Moved(player, written));
+        self.broadcast(ServerMessage::Position(self.board.to_notation()));
+        if self.clocks.as_ref().is_some_and(|c| c.running().is_some()) {
+            self.switch_clocks();
+        }
+    }
+}
+
+impl Server {
+    pub fn new(board: Board) -> Self {
+        let mut start = board.clone();
+        while start.undo_move().is_some() {}
+        Self {
+            game: Arc::new(Mutex::new(Game {
+                start,
+                board,
+                seats: [None; 3],
+                clients: HashMap::new(),
+                next_id: 0,
+                clocks: None,
+                epoch: Instant::now(),
+            })),
+        }
+    }
+
+    /// Like `new`, but with every player's time running by `control`.
+    pub fn with_clock(board: Board, control: TimeControl) -> Self {
+        let server = Self::new(board);
+        server.game.lock().unwrap().clocks = Some(Clocks::new(control));
+
+        // stops once the server is gone
+        let game = Arc::downgrade(&server.game);
+        std::thread::spawn(move || {
+            while let Some(game) = game.upgrade() {
+                game.lock().unwrap().check_time();
+                drop(game);
+                std::thread::sleep(FLAG_CHECK);
+            }
+        });
+        server
+    }
+
+    /// Accepts connections on `listener` forever, each on its own thread.
+    pub fn serve(&self, listener: TcpListener) {
+        self.accept(listener, |server, stream| {
+            let mut writer = stream.try_clone()?;
+            let lines = BufReader::new(stream).lines().map_while(Result::ok);
+            server.run(lines, move |message| writeln!(writer, "{message}"));
+            Ok(())
+        });
+    }
+
+    /// Like `serve`, but for WebSocket connections from browsers.
+    pub fn serve_websocket(&self, listener: TcpListener) {
+        self.accept(listener, |server, stream| {
+            let mut writer = stream.try_clone()?;
+            let mut reader = BufReader::new(stream);
+            websocket::accept(&mut reader, &mut writer)?;
+            let lines = std::iter::from_fn(move || websocket::read_text(&mut reader).ok().flatten());
+            server.run(lines, move |message| websocket::write_text(&mut writer, &message.to_string()));
+            Ok(())
+        });
+    }
+
+    fn accept(&self, listener: TcpListener,
+              handle: fn(&Server, TcpStream) -> io::Result<()>) {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let server = self.clone();
+            std::thread::spawn(move || handle(&server, stream));
+        }
+    }
+
+    /// Plays one connection, taking its messages from `lines` until they
+    /// end. The answers go to `send` on a thread of their own.
+    fn run(&self, lines: impl Iterator<Item = String>,
+           mut send: impl FnMut(&ServerMessage) -> io::Result<()> + Send + 'static) {
+        let (sender, receiver) = mpsc::channel::<ServerMessage>();
+        let id = self.game.lock().unwrap().connect(sender);
+
+        // stops once the connection is gone from `clients`
+        std::thread::spawn(move || {
+            for message in receiver {
+                if send(&message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut game = self.game.lock().unwrap();
+            match line.parse() {
+                Ok(message) => game.handle(id, message),
+                Err(e) => game.send(id, ServerMessage::Error(e.to_string())),
+            }
+        }
+        self.game.lock().unwrap().disconnect(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use std::time::Duration;
+
+    fn start_with(server: fn(Board) -> Server) -> std::net::SocketAddr {
+        let mut board = Board::new();
+        board.place_pieces();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || server(board).serve(listener));
+        addr
+    }
+
+    fn start() -> std::net::SocketAddr {
+        start_with(Server::new)
+    }
+
+    struct Connection {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Connection {
+        fn open(addr: std::net::SocketAddr) -> Self {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let mut connection = Self { writer: stream.try_clone().unwrap(), reader: BufReader::new(stream) };
+            assert_eq!(connection.receive(), ServerMessage::Rules(Rules::default()));
+            let mut next = connection.receive();
+            if matches!(next, ServerMessage::Clock(_)) {
+                next = connection.receive();
+            }
+            assert!(matches!(next, ServerMessage::Position(_)));
+            connection
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{line}").unwrap();
+        }
+
+        fn receive(&mut self) -> ServerMessage {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            line.parse().unwrap()
+        }
+    }
+
+    #[test]
+    fn seats_and_moves() {
+        let addr = start();
+        let mut red = Connection::open(addr);
+        let mut green = Connection::open(addr);
+
+        red.send("join red");
+        assert_eq!(red.receive(), ServerMessage::Joined(Player::Red));
+        green.send("join red");
+        assert_eq!(green.receive(), ServerMessage::Error("red is already taken".to_string()));
+        green.send("join green");
+        assert_eq!(green.receive(), ServerMessage::Joined(Player::Green));
+        green.send("join yellow");
+        assert_eq!(green.receive(), ServerMessage::Error("you already have a seat".to_string()));
+
+        green.send("move e7e5");
+        assert_eq!(green.receive(), ServerMessage::Error("it is not your turn".to_string()));
+        red.send("move e2e5");
+        assert!(matches!(red.receive(), ServerMessage::Error(_)));
+
+        red.send("move e2e4");
+        for c in [&mut red, &mut green] {
+            assert_eq!(c.receive(), ServerMessage::Moved(Player::Red, "e4".to_string()));
+            let ServerMessage::Position(position) = c.receive() else { panic!() };
+            assert_eq!(Board::from_notation(&position).unwrap().current_player(), Player::Green);
+        }
+    }
+
+    #[test]
+    fn spectators_catch_up_and_follow() {
+        let addr = start();
+        let mut red = Connection::open(addr);
+        red.send("join red");
+        assert_eq!(red.receive(), ServerMessage::Joined(Player::Red));
+        red.send("move e4");
+        assert_eq!(red.receive(), ServerMessage::Moved(Player::Red, "e4".to_string()));
+        let ServerMessage::Position(after_e4) = red.receive() else { panic!() };
+
+        // `open` takes the starting position
+        let mut spectator = Connection::open(addr);
+        assert_eq!(spectator.receive(), ServerMessage::Moved(Player::Red, "e4".to_string()));
+        assert_eq!(spectator.receive(), ServerMessage::Position(after_e4));
+
+        spectator.send("move Nc6");
+        assert_eq!(spectator.receive(), ServerMessage::Error("spectators cannot move".to_string()));
+
+        let mut green = Connection::open(addr);
+        for _ in 0 .. 2 {
+            green.receive();
+        }
+        green.send("join green");
+        assert_eq!(green.receive(), ServerMessage::Joined(Player::Green));
+        green.send("move Nc6");
+        assert_eq!(spectator.receive(), ServerMessage::Moved(Player::Green, "Nc6".to_string()));
+        assert!(matches!(spectator.receive(), ServerMessage::Position(_)));
+    }
+
+    #[test]
+    fn flag_fall() {
+        // a few tenths of a second for everyone
+        let addr = start_with(|board| Server::with_clock(board, "0.005".parse().unwrap()));
+        let mut seats = Player::ALL.map(|_| Connection::open(addr));
+        for (c, seat) in seats.iter_mut().zip(Player::ALL) {
+            assert!(matches!(c.receive(), ServerMessage::Time(_, None)));
+            c.send(&format!("join {seat}"));
+            assert_eq!(c.receive(), ServerMessage::Joined(seat));
+        }
+
+        // taking the last seat starts the clocks
+        let [red, _, yellow] = &mut seats;
+        assert!(matches!(yellow.receive(), ServerMessage::Time(_, Some(Player::Red))));
+        assert_eq!(yellow.receive(), ServerMessage::Flagged(Player::Red));
+        let ServerMessage::Position(position) = yellow.receive() else { panic!() };
+        assert!(Board::from_notation(&position).unwrap().is_out(Player::Red));
+        let ServerMessage::Time(left, running) = yellow.receive() else { panic!() };
+        assert_eq!((left[0], running), (Duration::ZERO, Some(Player::Green)));
+
+        while ! matches!(red.receive(), ServerMessage::Time(_, Some(Player::Green))) {}
+        red.send("move e4");
+        assert_eq!(red.receive(), ServerMessage::Error("it is not your turn".to_string()));
+    }
+
+    #[test]
+    fn leaving_frees_the_seat() {
+        let addr = start();
+        let mut first = Connection::open(addr);
+        first.send("join yellow");
+        assert_eq!(first.receive(), ServerMessage::Joined(Player::Yellow));
+        drop(first);
+
+        // the server notices the closed connection a little later
+        let mut second = Connection::open(addr);
+        for _ in 0 .. 100 {
+            second.send("join yellow");
+            if second.receive() == ServerMessage::Joined(Player::Yellow) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("yellow stayed taken");
+    }
+
+    #[test]
+    fn browsers_over_websocket() {
+        let mut board = Board::new();
+        board.place_pieces();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || Server::new(board).serve_websocket(listener));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        write!(stream, "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                        Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                        Sec-WebSocket-Version: 13\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut response = String::new();
+        while ! response.ends_with("\r\n\r\n") {
+            reader.read_line(&mut response).unwrap();
+        }
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+        let mut receive = || websocket::read_text(&mut reader).unwrap().unwrap().parse().unwrap();
+        assert!(matches!(receive(), ServerMessage::Rules(_)));
+        assert!(matches!(receive(), ServerMessage::Position(_)));
+
+        // clients mask their frames, here with a key of zeros
+        let text = "join red";
+        let mut frame = vec![0x81, 0x80 | text.len() as u8, 0, 0, 0, 0];
+        frame.extend_from_slice(text.as_bytes());
+        stream.write_all(&frame).unwrap();
+        assert_eq!(receive(), ServerMessage::Joined(Player::Red));
+    }
+
+    #[test]
+    fn bad_lines_are_answered() {
+        let mut connection = Connection::open(start());
+        connection.send("hello");
+        assert!(matches!(connection.receive(), ServerMessage::Error(_)));
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use crate::backend::{self, Color, Event, ImageHandle};
+use threechess::{COLOR_LETTERS, PIECE_LETTERS};
+
+/// Virtual pixels per character cell. Terminal cells are about twice as
+/// high as wide, so this keeps the board from looking squashed.
+const CELL_W: i32 = 4;
+const CELL_H: i32 = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    fg: Color,
+    bg: Color,
+}
+
+/// Draws into a grid of character cells with ANSI colours and takes moves
+/// as lines typed on standard input.
+///
+/// Drawing works on virtual pixels that are mapped onto the cells. Text is
+/// written into the cells it covers, and piece images are recognized by
+/// their file contents and shown as a piece and a colour letter, like
+/// `Nr` for a red knight.
+pub struct TerminalBackend {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    color: Color,
+    pieces: HashMap<Vec<u8>, (String, Color)>,
+    images: Vec<(String, Color)>,
+    lines: Receiver<String>,
+    last_frame: String,
+    start: std::time::Instant,
+}
+
+impl TerminalBackend {
+    pub fn new() -> Self {
+        let size = |var: &str, default: usize| std::env::var(var).ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default);
+        let cols = size("COLUMNS", 120);
+        // one line stays free for the prompt
+        let rows = size("LINES", 60).saturating_sub(1).max(1);
+
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut pieces = HashMap::new();
+        for (c, color) in COLOR_LETTERS.iter().zip([Color::RED, Color::GREEN, Color::YELLOW]) {
+            for p in PIECE_LETTERS {
+                if let Ok(data) = std::fs::read(format!("./assets/{p}{c}.png")) {
+                    pieces.insert(data, (format!("{}{c}", p.to_ascii_uppercase()), color));
+                }
+            }
+        }
+
+        let blank = Cell { ch: ' ', fg: Color::BLACK, bg: Color::WHITE };
+        Self {
+            cols,
+            rows,
+            cells: vec![blank; cols * rows],
+            color: Color::WHITE,
+            pieces,
+            images: vec![],
+            lines,
+            last_frame: String::new(),
+            start: std::time::Instant::now(),
+        }
+    }
+
+    fn cell(&mut self, x: i32, y: i32) -> Option<&mut Cell> {
+        let (cx, cy) = (x.div_euclid(CELL_W), y.div_euclid(CELL_H));
+        if cx < 0 || cy < 0 || cx as usize >= self.cols || cy as usize >= self.rows {
+            return None;
+        }
+        self.cells.get_mut(cy as usize * self.cols + cx as usize)
+    }
+
+    fn draw_point(&mut self, x: i32, y: i32) {
+        let color = self.color;
+        if let Some(cell) = self.cell(x, y) {
+            cell.bg = color;
+        }
+    }
+
+    /// Colours the cells whose centres lie in one of `spans`.
+    fn fill_spans(&mut self, spans: Vec<(i32, i32, i32)>) {
+        for (y, from, to) in spans {
+            if y.rem_euclid(CELL_H) != CELL_H / 2 {
+                continue;
+            }
+            for cx in from.div_euclid(CELL_W) ..= to.div_euclid(CELL_W) {
+                let x = cx * CELL_W + CELL_W / 2;
+                if (from ..= to).contains(&x) {
+                    self.draw_point(x, y);
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, text: &str, x: i32, y: i32, fg: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            if let Some(cell) = self.cell(x + i as i32 * CELL_W, y) {
+                cell.ch = ch;
+                cell.fg = fg;
+            }
+        }
+    }
+}
+
+impl backend::Backend for TerminalBackend {
+    fn draw_line(&mut self, a: (i32, i32), b: (i32, i32)) {
+        let steps = ((b.0 - a.0).abs() / CELL_W).max((b.1 - a.1).abs() / CELL_H).max(1);
+        let color = self.color;
+        for i in 0 ..= steps {
+            let x = a.0 + (b.0 - a.0) * i / steps;
+            let y = a.1 + (b.1 - a.1) * i / steps;
+            if let Some(cell) = self.cell(x, y) {
+                if cell.ch == ' ' {
+                    cell.ch = '.';
+                    cell.fg = color;
+                }
+            }
+        }
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn clear(&mut self) {
+        let blank = Cell { ch: ' ', fg: Color::BLACK, bg: self.color };
+        self.cells.fill(blank);
+    }
+
+    fn win_size(&self) -> (u32, u32) {
+        ((self.cols as i32 * CELL_W) as u32, (self.rows as i32 * CELL_H) as u32)
+    }
+
+    fn poll_event(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => events.push(Event::TextInput(line)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    events.push(Event::Quit);
+                    break;
+                },
+            }
+        }
+        events
+    }
+
+    fn fill_polygon(&mut self, points: &[(i32, i32)]) {
+        self.fill_spans(backend::polygon_spans(points));
+    }
+
+    fn fill_circle(&mut self, center: (i32, i32), radius: i32) {
+        self.fill_spans(backend::circle_spans(center, radius));
+    }
+
+    fn text_size(&self, string: &str) -> (u32, u32) {
+        ((string.chars().count() as i32 * CELL_W) as u32, CELL_H as u32)
+    }
+
+    fn render_text(&mut self, text: &str, x: i32, y: i32, color: Color) {
+        self.write(text, x, y, color);
+    }
+
+    fn load_image(&mut self, data: &[u8]) -> ImageHandle {
+        let label = self.pieces.get(data).cloned()
+            .unwrap_or_else(|| ("?".to_string(), Color::BLACK));
+        self.images.push(label);
+        ImageHandle(self.images.len() - 1)
+    }
+
+    fn draw_image(&mut self, image: ImageHandle, x: i32, y: i32, w: i32, h: i32) {
+        let (text, color) = self.images[image.0].clone();
+        let len = text.chars().count() as i32;
+        self.write(&text, x + w / 2 - len * CELL_W / 2, y + h / 2, color);
+    }
+
+    fn present(&mut self) {
+        let mut frame = String::from("\x1b[H\x1b[2J");
+        for row in self.cells.chunks(self.cols) {
+            let mut last = None;
+            for cell in row {
+                if last != Some((cell.fg, cell.bg)) {
+                    let Color(fr, fg, fb) = cell.fg;
+                    let Color(br, bg, bb) = cell.bg;
+                    frame += &format!("\x1b[38;2;{fr};{fg};{fb};48;2;{br};{bg};{bb}m");
+                    last = Some((cell.fg, cell.bg));
+                }
+                frame.push(cell.ch);
+            }
+            frame += "\x1b[0m\n";
+        }
+        frame += "move (e.g. e4, Nf3, e2e4), undo, redo or quit> ";
+
+        // the screen only changes after input, so there is no need to
+        // redraw it on every frame
+        if frame != self.last_frame {
+            print!("{frame}");
+            let _ = std::io::stdout().flush();
+            self.last_frame = frame;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(30));
+    }
+
+    fn ticks(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    fn log(string: String) {
+        println!("{}", string);
+    }
+
+    fn elog(string: String) {
+        eprintln!("{}", string);
+    }
+
+    fn read_file(&self, path: &str) -> Vec<u8> {
+        let mut f = std::fs::File::open(path).unwrap();
+        let mut data = vec![];
+        f.read_to_end(&mut data).unwrap();
+        data
+    }
+
+    fn write_file(&self, path: &str, data: &[u8]) {
+        if let Err(e) = std::fs::write(path, data) {
+            eprintln!("could not write {path}: {e}");
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Variants of the game, each with its own starting setup and rules.
+//!
+//! Each variant says what pawns promote to and whether they take en passant,
+//! all of the built-in ones keeping the usual rules there.
+//!
+//! - `standard` is the usual game.
+//! - `chess960-<n>` shuffles the pieces behind the pawns like Chess960, all
+//!   three players getting the same order, `n` from 0 to 959 telling which
+//!   of the setups, or a random one for a bare `chess960`. It plays without castling, as kings and rooks need not
+//!   stand where castling expects them, except in setup 518, which is the
+//!   standard one.
+//! - `king-of-the-hill` is also won by bringing the king to one of the six
+//!   fields around the middle of the board, the `HILL`.
+
+use crate::board::{pawn_home_rank, Board, Coord};
+use crate::castling::CastlingSide;
+use crate::piece::{Piece, PieceType, Player};
+use crate::rules::Rules;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    Standard,
+    Chess960(u16),
+    KingOfTheHill,
+}
+
+/// The fields around the middle of the board.
+pub const HILL: [Coord; 6] = [Coord('d', 4), Coord('e', 4), Coord('e', 9),
+                              Coord('i', 9), Coord('i', 5), Coord('d', 5)];
+
+/// Every player's home rank, and its files in the order `a` to `h` are for
+/// red: from the rook beside the queen to the one beside the king.
+const HOME: [(usize, [char; 8]); 3] = [
+    (1, ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h']),
+    (8, ['l', 'k', 'j', 'i', 'd', 'c', 'b', 'a']),
+    (12, ['h', 'g', 'f', 'e', 'i', 'j', 'k', 'l']),
+];
+
+/// The order of the pieces on the home rank in setup `n` of Chess960,
+/// numbered as usual so that 518 is the standard one.
+fn chess960_rank(n: u16) -> [PieceType; 8] {
+    let mut rank = [None; 8];
+    let mut n = n as usize % 960;
+
+    // the bishops go to fields of different colours
+    rank[n % 4 * 2 + 1] = Some(PieceType::Bishop);
+    n /= 4;
+    rank[n % 4 * 2] = Some(PieceType::Bishop);
+    n /= 4;
+
+    let place = |rank: &mut [Option<PieceType>; 8], i: usize, typ| {
+        let file = (0 .. 8).filter(|f| rank[*f].is_none()).nth(i).unwrap();
+        rank[file] = Some(typ);
+    };
+    place(&mut rank, n % 6, PieceType::Queen);
+    n /= 6;
+
+    const KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2),
+                                           (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+    let (first, second) = KNIGHTS[n];
+    place(&mut rank, second, PieceType::Knight);
+    place(&mut rank, first, PieceType::Knight);
+
+    // the king between the rooks
+    for typ in [PieceType::Rook, PieceType::King, PieceType::Rook] {
+        place(&mut rank, 0, typ);
+    }
+    rank.map(Option::unwrap)
+}
+
+impl Variant {
+    /// Reads a variant like `from_str`, taking a bare `chess960` for the
+    /// setup `seed` picks.
+    pub fn parse_or_random(s: &str, seed: u64) -> Result<Variant, String> {
+        match s {
+            "chess960" => Ok(Variant::Chess960((seed % 960) as u16)),
+            _ => s.parse(),
+        }
+    }
+
+    /// The starting position, playing by this variant.
+    pub fn setup(&self) -> Board {
+        let mut board = Board::new();
+        match self {
+            Variant::Chess960(n) => {
+                let rank = chess960_rank(*n);
+                for player in Player::ALL {
+                    let (home, files) = HOME[player as usize];
+                    for (file, typ) in files.into_iter().zip(rank) {
+                        board.get_field_mut(file, home).unwrap().piece = Some(Piece { typ, player });
+                        board.get_field_mut(file, pawn_home_rank(player)).unwrap().piece =
+                            Some(Piece { typ: PieceType::Pawn, player });
+                    }
+                }
+            },
+            Variant::Standard | Variant::KingOfTheHill => board.place_pieces(),
+        }
+
+        for player in Player::ALL {
+            for side in [CastlingSide::King, CastlingSide::Queen] {
+                board.set_castling_right(player, side, self.castling());
+            }
+        }
+        board.set_rules(Rules { variant: *self, ..board.rules() });
+        board
+    }
+
+    /// What a pawn may turn into on reaching a far end.
+    pub fn promotions(&self) -> &'static [PieceType] {
+        &PieceType::PROMOTIONS
+    }
+
+    /// Whether the players start with their castling rights.
+    pub fn castling(&self) -> bool {
+        ! matches!(self, Variant::Chess960(n) if *n != 518)
+    }
+
+    /// Whether a pawn's double step may be taken en passant.
+    pub fn en_passant(&self) -> bool {
+        true
+    }
+
+    /// The fields that win the game for a king reaching them.
+    pub fn hill(&self) -> &'static [Coord] {
+        match self {
+            Variant::KingOfTheHill => &HILL,
+            _ => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "standard"),
+            Variant::Chess960(n) => write!(f, "chess960-{n}"),
+            Variant::KingOfTheHill => write!(f, "king-of-the-hill"),
+        }
+    }
+}
+
+impl std::str::FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("unknown variant '{s}', expected standard, chess960-<0 to 959> \
+                                  or king-of-the-hill");
+        match s {
+            "standard" => Ok(Variant::Standard),
+            "king-of-the-hill" => Ok(Variant::KingOfTheHill),
+            _ => match s.strip_prefix("chess960-").and_then(|n| n.parse().ok()) {
+                Some(n) if n < 960 => Ok(Variant::Chess960(n)),
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameOutcome;
+
+    #[test]
+    fn chess960_setups() {
+        let standard = [PieceType::Rook, PieceType::Knight, PieceType::Bishop, PieceType::Queen,
+                        PieceType::King, PieceType::Bishop, PieceType::Knight, PieceType::Rook];
+        assert_eq!(chess960_rank(518), standard);
+        assert_eq!(Variant::Chess960(518).setup().to_notation(), Variant::Standard.setup().to_notation());
+        assert!(Variant::Chess960(0).setup().to_notation().ends_with(" r - - -"));
+
+        for n in 0 .. 960 {
+            let rank = chess960_rank(n);
+            let find = |typ| (0 .. 8).filter(|i| rank[*i] == typ).collect::<Vec<_>>();
+            let (rooks, king, bishops) = (find(PieceType::Rook), find(PieceType::King)[0],
+                                          find(PieceType::Bishop));
+            assert!(rooks[0] < king && king < rooks[1], "{n}");
+
+            // every player's bishops stand on fields of both colours
+            let board = Variant::Chess960(n).setup();
+            for (home, files) in HOME {
+                let [a, b] = [0, 1].map(|i| board.get_field(files[bishops[i]], home).unwrap().typ);
+                assert!(a != b, "{n}");
+            }
+            assert!(board.legal_moves(Player::Red).len() >= 16);
+        }
+    }
+
+    #[test]
+    fn variant_names() {
+        for v in [Variant::Standard, Variant::Chess960(7), Variant::KingOfTheHill] {
+            assert_eq!(v.to_string().parse(), Ok(v));
+        }
+        for bad in ["", "chess960", "chess960-960", "hill"] {
+            assert!(bad.parse::<Variant>().is_err(), "{bad}");
+        }
+
+        assert_eq!(Variant::parse_or_random("chess960", 1000), Ok(Variant::Chess960(40)));
+        assert_eq!(Variant::parse_or_random("chess960-7", 1000), Ok(Variant::Chess960(7)));
+        assert_eq!(Variant::parse_or_random("standard", 1000), Ok(Variant::Standard));
+        assert!(Variant::parse_or_random("hill", 1000).is_err());
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let mut board = Board::from_notation("8/8/4kr3/8/8/8/8/kg7/8/8/8/7ky r - - -").unwrap();
+        board.set_rules(Rules { variant: Variant::KingOfTheHill, ..Rules::default() });
+        board.apply_move(board.parse_move("Kd4").unwrap()).unwrap();
+        assert_eq!(board.outcome(), Some(GameOutcome::Won(Player::Red)));
+        assert_eq!(board.current_player(), Player::Red);
+        assert_eq!(board.ranking(), [(1, Player::Red), (2, Player::Green), (2, Player::Yellow)]);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::backend::{Backend,MouseButton,Event};
+use std::io;
+
+use crate::backend::{Backend,MouseButton,Event,ImageHandle,Key,Modifiers};
+use threechess::{ClientMessage, ServerMessage};
 
 pub struct WasmBackend;
 
@@ -13,6 +16,7 @@ extern "C" {
     fn _elog(ptr: *const u8, len: usize);
     fn _read_file(ptr: *const u8, len: usize, buf: *mut u8, buf_len: usize);
     fn _get_file_size(ptr: *const u8, len: usize) -> usize;
+    fn _write_file(ptr: *const u8, len: usize, buf: *const u8, buf_len: usize);
     fn _set_draw_color(r: u8, g: u8, b: u8);
     fn _clear();
     fn _win_size(w: *mut u32, h: *mut u32);
@@ -21,10 +25,99 @@ extern "C" {
     fn _text_size(txt_ptr: *const u8, txt_len: usize, w: *mut u32, h: *mut u32);
     fn _render_text(txt_ptr: *const u8, txt_len: usize, x: i32, y: i32,
                     r: u8, g: u8, b: u8);
-    fn _draw_point(x: i32, y: i32);
     fn _draw_line(x1: i32, y1: i32, x2: i32, y2: i32);
-    fn _render_png(data_ptr: *const u8, data_len: usize, x: i32, y: i32, w: i32, h: i32);
+    fn _fill_polygon(points_ptr: *const i32, points_len: usize);
+    fn _fill_circle(x: i32, y: i32, r: i32);
+    fn _load_image(data_ptr: *const u8, data_len: usize) -> usize;
+    fn _draw_image(id: usize, x: i32, y: i32, w: i32, h: i32);
     fn _present();
+    fn _ticks() -> f64;
+    fn _args_size() -> usize;
+    fn _get_args(data: *mut u8);
+    fn _ws_connect(url_ptr: *const u8, url_len: usize);
+    fn _ws_send(ptr: *const u8, len: usize);
+    fn _ws_queue_size() -> usize;
+    fn _get_ws_queue(data: *mut u8);
+    fn _ws_closed() -> bool;
+}
+
+/// The page's query parameters as command line arguments, `?seat=red`
+/// becoming `--seat red`.
+pub fn args() -> Vec<String> {
+    let len = unsafe {_args_size()};
+    let mut data = vec![0; len];
+    unsafe {_get_args(data.as_mut_ptr())};
+    String::from_utf8_lossy(&data).lines().map(String::from).collect()
+}
+
+/// Some number that differs from one page load to the next, to pick a
+/// random variant with.
+pub fn seed() -> u64 {
+    (unsafe {_ticks()} * 1000.0) as u64
+}
+
+/// A connection to `threechess-server` through a WebSocket of the browser,
+/// offering the same methods as `threechess::Client`.
+pub struct WebSocketClient;
+
+impl WebSocketClient {
+    /// Connects to `ws://<address>`, or to `address` itself if it has a
+    /// scheme like `wss://`. Messages sent before the connection is open
+    /// wait in `main.js`.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let url = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("ws://{address}")
+        };
+        unsafe {_ws_connect(url.as_ptr(), url.len())};
+        Ok(Self)
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        let text = message.to_string();
+        unsafe {_ws_send(text.as_ptr(), text.len())};
+        Ok(())
+    }
+
+    /// The messages that arrived since the last call, one per line in the
+    /// queue of `main.js`.
+    pub fn poll(&mut self) -> io::Result<Vec<ServerMessage>> {
+        let len = unsafe {_ws_queue_size()};
+        if len == 0 && unsafe {_ws_closed()} {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut data = vec![0; len];
+        unsafe {_get_ws_queue(data.as_mut_ptr())};
+        String::from_utf8_lossy(&data).lines()
+            .map(|line| line.parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+            .collect()
+    }
+}
+
+/// Keys arrive from `main.js` as their character code, or as one of these
+/// negative codes for keys without a character.
+fn key_from_code(code: i32) -> Key {
+    match code {
+        -1 => Key::Enter,
+        -2 => Key::Escape,
+        -3 => Key::Backspace,
+        -4 => Key::Tab,
+        -5 => Key::Left,
+        -6 => Key::Right,
+        -7 => Key::Up,
+        -8 => Key::Down,
+        c if c >= 0 => char::from_u32(c as u32).map_or(Key::Unknown, Key::Char),
+        _ => Key::Unknown,
+    }
+}
+
+fn modifiers_from_bits(bits: i32) -> Modifiers {
+    Modifiers {
+        ctrl: bits & 1 != 0,
+        shift: bits & 2 != 0,
+        alt: bits & 4 != 0,
+    }
 }
 
 macro_rules! read_i32 {
@@ -71,16 +164,38 @@ impl Backend for WasmBackend {
         let mut events = vec![];
 
         const EVENT_TYPE_MOUSE_UP: i32 = 0;
+        const EVENT_TYPE_KEY_DOWN: i32 = 1;
+        const EVENT_TYPE_KEY_UP: i32 = 2;
+        const EVENT_TYPE_MOUSE_DOWN: i32 = 3;
+        const EVENT_TYPE_MOUSE_MOVE: i32 = 4;
         let mut idx = 0;
         while idx < data.len() {
             let tp = read_i32!(data, idx);
             match tp {
-                EVENT_TYPE_MOUSE_UP => {
+                EVENT_TYPE_MOUSE_UP | EVENT_TYPE_MOUSE_DOWN => {
                     let btn = read_i32!(data, idx);
                     assert_eq!(btn, 0);
                     let x = read_i32!(data, idx);
                     let y = read_i32!(data, idx);
-                    events.push(Event::MouseButtonUp(MouseButton::Left, x, y));
+                    events.push(if tp == EVENT_TYPE_MOUSE_UP {
+                        Event::MouseButtonUp(MouseButton::Left, x, y)
+                    } else {
+                        Event::MouseButtonDown(MouseButton::Left, x, y)
+                    });
+                },
+                EVENT_TYPE_MOUSE_MOVE => {
+                    let x = read_i32!(data, idx);
+                    let y = read_i32!(data, idx);
+                    events.push(Event::MouseMotion(x, y));
+                },
+                EVENT_TYPE_KEY_DOWN | EVENT_TYPE_KEY_UP => {
+                    let key = key_from_code(read_i32!(data, idx));
+                    let modifiers = modifiers_from_bits(read_i32!(data, idx));
+                    events.push(if tp == EVENT_TYPE_KEY_DOWN {
+                        Event::KeyDown(key, modifiers)
+                    } else {
+                        Event::KeyUp(key, modifiers)
+                    });
                 },
                 _ => unreachable!("invalid event type: {tp}"),
             }
@@ -88,8 +203,13 @@ impl Backend for WasmBackend {
         events
     }
 
-    fn draw_point(&mut self, x: i32, y: i32) {
-        unsafe {_draw_point(x, y)};
+    fn fill_polygon(&mut self, points: &[(i32, i32)]) {
+        let coords: Vec<i32> = points.iter().flat_map(|p| [p.0, p.1]).collect();
+        unsafe {_fill_polygon(coords.as_ptr(), points.len())};
+    }
+
+    fn fill_circle(&mut self, center: (i32, i32), radius: i32) {
+        unsafe {_fill_circle(center.0, center.1, radius)};
     }
 
     fn text_size(&self, string: &str) -> (u32, u32) {
@@ -106,14 +226,23 @@ impl Backend for WasmBackend {
                     color.0, color.1, color.2)};
     }
 
-    fn render_png_data(&mut self, data: &[u8], x: i32, y: i32, w: i32, h: i32) {
-        unsafe {_render_png(data.as_ptr(), data.len(), x, y, w, h)};
+    fn load_image(&mut self, data: &[u8]) -> ImageHandle {
+        ImageHandle(unsafe {_load_image(data.as_ptr(), data.len())})
+    }
+
+    fn draw_image(&mut self, image: ImageHandle, x: i32, y: i32, w: i32, h: i32) {
+        unsafe {_draw_image(image.0, x, y, w, h)};
     }
 
     fn present(&mut self) {
         unsafe {_present()};
     }
 
+    fn ticks(&self) -> u64 {
+        let ms = unsafe {_ticks()};
+        ms as u64
+    }
+
     fn log(string: String) {
         unsafe {_log(string.as_ptr(), string.bytes().count())};
     }
@@ -131,4 +260,9 @@ impl Backend for WasmBackend {
                            buf.as_mut_ptr(), size)};
         buf
     }
+
+    fn write_file(&self, path: &str, data: &[u8]) {
+        unsafe {_write_file(path.as_ptr(), path.bytes().count(),
+                            data.as_ptr(), data.len())};
+    }
 }
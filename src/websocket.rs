@@ -0,0 +1,240 @@
+//! Just enough of WebSocket (RFC 6455) for a server to exchange text
+//! messages with browsers: the opening handshake and unfragmented text
+//! frames going out, any text frames coming in.
+
+use std::io::{self, BufRead, Read, Write};
+
+/// Longest message accepted from a client; the protocol only has short ones.
+const MAX_MESSAGE: u64 = 1 << 16;
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (wi, bytes) in w.iter_mut().zip(chunk.chunks(4)) {
+            *wi = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in 16 .. 80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, wi) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0 ..= 19 => ((b & c) | (! b & d), 0x5a827999),
+                20 ..= 39 => (b ^ c ^ d, 0x6ed9eba1),
+                40 ..= 59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let t = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*wi);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (x, y) in h.iter_mut().zip([a, b, c, d, e]) {
+            *x = x.wrapping_add(y);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, x) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&x.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0 .. 4 {
+            if i <= chunk.len() {
+                s.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// The `Sec-WebSocket-Accept` answer to a client's `Sec-WebSocket-Key`.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{GUID}").as_bytes()))
+}
+
+/// Reads the HTTP request opening a connection and agrees to it.
+pub fn accept(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<()> {
+    let mut key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let Some(key) = key else {
+        write!(writer, "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a WebSocket request"));
+    };
+    write!(writer, "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                    Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n", accept_key(&key))
+}
+
+/// Reads the next text message, or `None` once the other side closes the
+/// connection. Binary messages, pings and pongs are skipped; browsers do not
+/// send pings on their own, so they go unanswered.
+pub fn read_text(reader: &mut impl Read) -> io::Result<Option<String>> {
+    let mut message = vec![];
+    // whether the continuations coming belong to a skipped binary message
+    let mut binary = false;
+    loop {
+        let mut head = [0; 2];
+        reader.read_exact(&mut head)?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0f;
+
+        let len = match head[1] & 0x7f {
+            126 => {
+                let mut bytes = [0; 2];
+                reader.read_exact(&mut bytes)?;
+                u16::from_be_bytes(bytes) as u64
+            },
+            127 => {
+                let mut bytes = [0; 8];
+                reader.read_exact(&mut bytes)?;
+                u64::from_be_bytes(bytes)
+            },
+            n => n as u64,
+        };
+        if len > MAX_MESSAGE - message.len() as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "message too long"));
+        }
+
+        let mut mask = [0; 4];
+        if head[1] & 0x80 != 0 {
+            reader.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0; len as usize];
+        reader.read_exact(&mut payload)?;
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= mask[i % 4];
+        }
+
+        match opcode {
+            0x8 => return Ok(None),
+            0x2 => {
+                binary = ! fin;
+                continue;
+            },
+            0x0 if binary => {
+                binary = ! fin;
+                continue;
+            },
+            // text and its continuations
+            0x0 | 0x1 => message.extend(payload),
+            _ => continue,
+        }
+        if fin {
+            return String::from_utf8(message).map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+    }
+}
+
+/// Sends `text` as one text message.
+pub fn write_text(writer: &mut impl Write, text: &str) -> io::Result<()> {
+    let mut frame = vec![0x81];
+    match text.len() {
+        len @ 0 ..= 125 => frame.push(len as u8),
+        len @ 126 ..= 0xffff => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        },
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        },
+    }
+    frame.extend_from_slice(text.as_bytes());
+    writer.write_all(&frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_answer() {
+        // the example from RFC 6455
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn masked_and_fragmented_frames() {
+        // "Hello" masked, then "Hel" and "lo" in two frames, from RFC 6455
+        let data = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+                    0x01, 0x03, 0x48, 0x65, 0x6c, 0x80, 0x02, 0x6c, 0x6f, 0x88, 0x00];
+        let mut reader = &data[..];
+        assert_eq!(read_text(&mut reader).unwrap().as_deref(), Some("Hello"));
+        assert_eq!(read_text(&mut reader).unwrap().as_deref(), Some("Hello"));
+        assert_eq!(read_text(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn fragmented_binary_skipped() {
+        // a binary message in two frames, its continuation not valid UTF-8,
+        // then a text message
+        let data = [0x02, 0x02, 0xff, 0xfe, 0x80, 0x01, 0xff, 0x81, 0x02, b'o', b'k'];
+        assert_eq!(read_text(&mut &data[..]).unwrap().as_deref(), Some("ok"));
+
+        // the same with a ping between the fragments
+        let data = [0x02, 0x01, 0xff, 0x89, 0x00, 0x80, 0x01, 0xff, 0x81, 0x02, b'o', b'k'];
+        assert_eq!(read_text(&mut &data[..]).unwrap().as_deref(), Some("ok"));
+    }
+
+    #[test]
+    fn long_messages_read_back() {
+        for len in [0, 125, 126, 300, 70000] {
+            let text = "x".repeat(len);
+            let mut frame = vec![];
+            write_text(&mut frame, &text).unwrap();
+            let result = read_text(&mut &frame[..]);
+            if len as u64 > MAX_MESSAGE {
+                assert!(result.is_err());
+            } else {
+                assert_eq!(result.unwrap(), Some(text));
+            }
+        }
+
+        // a continuation claiming the largest length there is
+        let mut data = vec![0x01, 0x03, b'a', b'b', b'c', 0x80, 0x7f];
+        data.extend(u64::MAX.to_be_bytes());
+        assert!(read_text(&mut &data[..]).is_err());
+    }
+}
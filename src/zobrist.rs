@@ -0,0 +1,99 @@
+//! Zobrist hashing of positions, to recognise repetitions.
+//!
+//! Every piece on every field, the player to move, each player being out,
+//! each castling right and each field open for en passant has a random key,
+//! and a position hashes to the xor of the keys of everything in it. The
+//! keys come from a fixed seed, so hashes stay the same from run to run.
+
+use std::sync::OnceLock;
+
+use crate::board::Board;
+use crate::castling::CastlingSide;
+use crate::indexed::FIELD_COUNT;
+use crate::piece::Player;
+
+struct Keys {
+    /// `pieces[field][player * 6 + piece type]`.
+    pieces: [[u64; 18]; FIELD_COUNT],
+    to_move: [u64; 3],
+    out: [u64; 3],
+    castling: [[u64; 2]; 3],
+    en_passant: [[u64; FIELD_COUNT]; 3],
+}
+
+/// The splitmix64 generator, good enough for keys that only have to differ.
+fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn keys() -> &'static Keys {
+    static KEYS: OnceLock<Keys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut state = 0x3c3e55;
+        let mut key = || next_key(&mut state);
+        Keys {
+            pieces: [(); FIELD_COUNT].map(|_| [(); 18].map(|_| key())),
+            to_move: [(); 3].map(|_| key()),
+            out: [(); 3].map(|_| key()),
+            castling: [(); 3].map(|_| [key(), key()]),
+            en_passant: [(); 3].map(|_| [(); FIELD_COUNT].map(|_| key())),
+        }
+    })
+}
+
+/// The hash of the position on `board`, not counting how it came about.
+pub fn hash(board: &Board) -> u64 {
+    let keys = keys();
+    let mut hash = keys.to_move[board.current_player() as usize];
+
+    for s in board.sections() {
+        for f in s.fields.iter().flatten() {
+            if let Some(p) = f.piece {
+                hash ^= keys.pieces[f.coord.index()][p.player as usize * 6 + p.typ as usize];
+            }
+        }
+    }
+
+    for player in Player::ALL {
+        let i = player as usize;
+        if board.is_out(player) {
+            hash ^= keys.out[i];
+        }
+        for side in [CastlingSide::King, CastlingSide::Queen] {
+            if board.has_castling_right(player, side) {
+                hash ^= keys.castling[i][side as usize];
+            }
+        }
+        if let Some(e) = board.en_passant(player) {
+            hash ^= keys.en_passant[i][e.target.index()];
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_position_same_hash() {
+        let mut board = Board::new();
+        board.place_pieces();
+        let start = hash(&board);
+
+        let mut other = board.clone();
+        for s in ["Nf3", "Nc6", "Nf10", "Ng1", "Nb8"] {
+            other.apply_move(other.parse_move(s).unwrap()).unwrap();
+            assert_ne!(hash(&other), start, "{s}");
+        }
+        other.apply_move(other.parse_move("Ng12").unwrap()).unwrap();
+        assert_eq!(hash(&other), start);
+
+        board.set_current_player(Player::Green);
+        assert_ne!(hash(&board), start);
+    }
+}